@@ -1,9 +1,107 @@
+use pyo3::create_exception;
 use pyo3::exceptions;
 use pyo3::prelude::*;
 use pyo3::type_object::PyTypeInfo;
 use std::ffi::CString;
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use tokenizers::tokenizer::Result;
+use tokenizers::tokenizer::{Error as TkError, Result};
+
+create_exception!(
+    tokenizers,
+    TokenizerError,
+    exceptions::PyException,
+    "Base class for all errors raised by the `tokenizers` core."
+);
+create_exception!(
+    tokenizers,
+    TruncationError,
+    TokenizerError,
+    "Raised when a truncation strategy or parameters are invalid."
+);
+create_exception!(
+    tokenizers,
+    PaddingError,
+    TokenizerError,
+    "Raised when a padding strategy or parameters are invalid."
+);
+create_exception!(
+    tokenizers,
+    DeserializationError,
+    TokenizerError,
+    "Raised when a tokenizer, model or component fails to load from its serialized form."
+);
+create_exception!(
+    tokenizers,
+    VocabError,
+    TokenizerError,
+    "Raised when a vocabulary is missing an expected entry (e.g. a special token)."
+);
+create_exception!(
+    tokenizers,
+    ModelError,
+    TokenizerError,
+    "Raised when a model fails to build or run (e.g. an invalid BPE/WordPiece/Unigram config)."
+);
+
+/// A coarse guess at what went wrong, used to pick the `TokenizerError` subclass that
+/// `ToPyResult` raises. `tokenizers::tokenizer::Error` is a type-erased `Box<dyn
+/// Error>`, so there's no variant to match on here; this only looks at the rendered
+/// message. Treat it as a best-effort classification, not an exhaustive one: messages
+/// that don't match any of these keywords fall back to the `TokenizerError` base
+/// class, and a message that happens to mention e.g. "vocab" in passing could be
+/// misclassified.
+enum ErrorKind {
+    Truncation,
+    Padding,
+    Deserialization,
+    Vocab,
+    Model,
+    Other,
+}
+
+impl ErrorKind {
+    fn classify(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if message.contains("truncat") {
+            Self::Truncation
+        } else if message.contains("pad") {
+            Self::Padding
+        } else if message.contains("deserial") || message.contains("serde") {
+            Self::Deserialization
+        } else if message.contains("vocab") || message.contains("special token") {
+            Self::Vocab
+        } else if message.contains("model") {
+            Self::Model
+        } else {
+            Self::Other
+        }
+    }
+
+    fn into_pyerr(self, message: String) -> PyErr {
+        match self {
+            Self::Truncation => TruncationError::new_err(message),
+            Self::Padding => PaddingError::new_err(message),
+            Self::Deserialization => DeserializationError::new_err(message),
+            Self::Vocab => VocabError::new_err(message),
+            Self::Model => ModelError::new_err(message),
+            Self::Other => TokenizerError::new_err(message),
+        }
+    }
+}
+
+/// Registers `TokenizerError` and its subclasses on the `tokenizers` module.
+pub(crate) fn register_exceptions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("TokenizerError", m.py().get_type::<TokenizerError>())?;
+    m.add("TruncationError", m.py().get_type::<TruncationError>())?;
+    m.add("PaddingError", m.py().get_type::<PaddingError>())?;
+    m.add(
+        "DeserializationError",
+        m.py().get_type::<DeserializationError>(),
+    )?;
+    m.add("VocabError", m.py().get_type::<VocabError>())?;
+    m.add("ModelError", m.py().get_type::<ModelError>())?;
+    Ok(())
+}
 
 #[derive(Debug)]
 pub struct PyError(pub String);
@@ -23,10 +121,23 @@ impl Display for PyError {
 }
 impl std::error::Error for PyError {}
 
+/// Converts one error in a `source()` chain, then recurses onto whatever it was
+/// caused by, linking each step to the previous one via `PyErr::set_cause` so Python
+/// renders the familiar "The above exception was the direct cause of the following
+/// exception" chain instead of a single flattened message.
+fn chain_to_pyerr(py: Python<'_>, err: &(dyn std::error::Error + 'static)) -> PyErr {
+    let message = err.to_string();
+    let pyerr = ErrorKind::classify(&message).into_pyerr(message);
+    if let Some(source) = err.source() {
+        pyerr.set_cause(py, Some(chain_to_pyerr(py, source)));
+    }
+    pyerr
+}
+
 pub struct ToPyResult<T>(pub Result<T>);
 impl<T> From<ToPyResult<T>> for PyResult<T> {
     fn from(v: ToPyResult<T>) -> Self {
-        v.0.map_err(|e| exceptions::PyException::new_err(format!("{e}")))
+        v.0.map_err(|e| Python::with_gil(|py| chain_to_pyerr(py, e.as_ref())))
     }
 }
 impl<T> ToPyResult<T> {
@@ -35,8 +146,114 @@ impl<T> ToPyResult<T> {
     }
 }
 
-pub(crate) fn deprecation_warning(py: Python<'_>, version: &str, message: &str) -> PyResult<()> {
-    let deprecation_warning = py.import("builtins")?.getattr("DeprecationWarning")?;
-    let full_message = format!("Deprecated in {version}: {message}");
-    pyo3::PyErr::warn(py, &deprecation_warning, &CString::new(full_message)?, 0)
+/// A `major.minor.patch` triple, parsed just well enough to order two version
+/// strings; missing/non-numeric components default to `0`. Not a full semver
+/// implementation (no pre-release/build metadata) since all we need here is to
+/// compare a schedule against `CARGO_PKG_VERSION`.
+fn parse_version(version: &str) -> (u64, u64, u64) {
+    let mut parts = version.split('.').map(|p| p.parse().unwrap_or(0));
+    (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    )
+}
+
+/// The removal schedule for a deprecated binding, so every call site declares both
+/// when it started warning and (if known) when it stops working, instead of just a
+/// version string that only ever produces a plain `DeprecationWarning`.
+pub(crate) enum DeprecatedSince {
+    /// Deprecated as of `since`; no removal version has been decided yet.
+    Unspecified { since: &'static str },
+    /// Deprecated as of `since`, scheduled for removal in `removed_in`.
+    Scheduled {
+        since: &'static str,
+        removed_in: &'static str,
+    },
+}
+
+impl DeprecatedSince {
+    pub(crate) fn unspecified(since: &'static str) -> Self {
+        Self::Unspecified { since }
+    }
+
+    pub(crate) fn scheduled(since: &'static str, removed_in: &'static str) -> Self {
+        Self::Scheduled { since, removed_in }
+    }
+
+    fn since(&self) -> &'static str {
+        match self {
+            Self::Unspecified { since } | Self::Scheduled { since, .. } => since,
+        }
+    }
+
+    fn removed_in(&self) -> Option<&'static str> {
+        match self {
+            Self::Unspecified { .. } => None,
+            Self::Scheduled { removed_in, .. } => Some(removed_in),
+        }
+    }
+}
+
+/// Warns (or, once a feature's removal version has shipped, raises) about a
+/// deprecated binding. The Python warning class escalates with how close `since`/
+/// `removed_in` are to the crate's current version: `PendingDeprecationWarning`
+/// while removal is still scheduled for a future release the crate hasn't reached
+/// `since` for yet, `DeprecationWarning` once `since` has been reached, and an
+/// outright `TokenizerError` once the crate has reached `removed_in`.
+pub(crate) fn deprecation_warning(
+    py: Python<'_>,
+    schedule: DeprecatedSince,
+    message: &str,
+) -> PyResult<()> {
+    let current = parse_version(env!("CARGO_PKG_VERSION"));
+    let full_message = format!("Deprecated in {}: {message}", schedule.since());
+
+    if let Some(removed_in) = schedule.removed_in() {
+        if current >= parse_version(removed_in) {
+            return Err(TokenizerError::new_err(format!(
+                "Removed in {removed_in}: {message}"
+            )));
+        }
+    }
+
+    let warning_class = if current < parse_version(schedule.since()) {
+        "PendingDeprecationWarning"
+    } else {
+        "DeprecationWarning"
+    };
+    let warning_class = py.import("builtins")?.getattr(warning_class)?;
+    pyo3::PyErr::warn(py, &warning_class, &CString::new(full_message)?, 0)
+}
+
+/// Builds a PEP 654 `ExceptionGroup` out of a batch's per-item failures, one typed
+/// sub-exception per `(index, error)` pair (classified and chained exactly like
+/// [`ToPyResult`]), each annotated with its original index via `add_note`. This backs
+/// an opt-in aggregating mode for batch operations such as `encode_batch`/
+/// `encode_batch_fast`: instead of returning on the first failure, the caller runs the
+/// whole batch, collects every failing `(index, error)`, and raises the group built
+/// here so a single `except*` can see (and handle) every failure from one call.
+pub(crate) fn into_exception_group(
+    py: Python<'_>,
+    failures: Vec<(usize, TkError)>,
+) -> PyResult<PyErr> {
+    let count = failures.len();
+    let sub_exceptions = failures
+        .into_iter()
+        .map(|(index, err)| {
+            let pyerr = chain_to_pyerr(py, err.as_ref());
+            let instance = pyerr.into_value(py);
+            instance
+                .bind(py)
+                .call_method1("add_note", (format!("input index {index} failed"),))?;
+            Ok(instance)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let exception_group = py.import("builtins")?.getattr("ExceptionGroup")?;
+    let group = exception_group.call1((
+        format!("{count} inputs failed to encode"),
+        sub_exceptions,
+    ))?;
+    Ok(PyErr::from_value(group))
 }
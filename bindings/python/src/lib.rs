@@ -0,0 +1,24 @@
+#![allow(clippy::borrow_deref_ref)]
+
+mod error;
+mod models;
+mod tokenizer;
+
+use pyo3::prelude::*;
+use pyo3::wrap_pymodule;
+
+/// Tokenizers Python bindings entry point. Registers every class exposed to Python
+/// on the `tokenizers` module, then layers the typed exception hierarchy on top so
+/// `except tokenizers.TokenizerError` (and its subclasses) works without anyone having
+/// to import a separate errors module.
+#[pymodule]
+fn tokenizers(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<tokenizer::PyTokenizer>()?;
+    m.add_wrapped(wrap_pymodule!(models::models))?;
+
+    error::register_exceptions(m)?;
+
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+
+    Ok(())
+}
@@ -4,7 +4,7 @@ use std::sync::{Arc, RwLock};
 
 use crate::token::PyToken;
 use crate::trainers::PyTrainer;
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use pyo3::exceptions;
 use pyo3::prelude::*;
 use pyo3::types::*;
@@ -17,7 +17,172 @@ use tk::models::ModelWrapper;
 use tk::{Model, Token};
 use tokenizers as tk;
 
-use super::error::{deprecation_warning, ToPyResult};
+use super::error::{deprecation_warning, DeprecatedSince, ToPyResult};
+
+/// A minimal reader for the SentencePiece `ModelProto` protobuf, just enough to pull
+/// `(piece, score, type)` out of its `pieces` field and `unk_id` out of its
+/// `trainer_spec`, without pulling in a full protobuf codegen dependency. Unknown
+/// fields (at any nesting level) are skipped generically based on their wire type, so
+/// this stays forward-compatible with proto fields this module doesn't care about.
+mod spm {
+    /// `SentencePiece.Type`, mirroring the proto enum (`UNUSED` is never produced by
+    /// real SentencePiece models but is kept for completeness).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum PieceType {
+        Normal,
+        Unknown,
+        Control,
+        UserDefined,
+        Unused,
+        Byte,
+    }
+
+    impl PieceType {
+        fn from_i64(v: i64) -> Self {
+            match v {
+                2 => Self::Unknown,
+                3 => Self::Control,
+                4 => Self::UserDefined,
+                5 => Self::Unused,
+                6 => Self::Byte,
+                _ => Self::Normal,
+            }
+        }
+    }
+
+    pub(crate) struct Piece {
+        pub piece: String,
+        pub score: f32,
+        pub kind: PieceType,
+    }
+
+    pub(crate) struct ModelProto {
+        pub pieces: Vec<Piece>,
+        pub trainer_spec_unk_id: Option<i64>,
+    }
+
+    struct Reader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn new(buf: &'a [u8]) -> Self {
+            Self { buf, pos: 0 }
+        }
+
+        fn eof(&self) -> bool {
+            self.pos >= self.buf.len()
+        }
+
+        fn read_varint(&mut self) -> Result<u64, String> {
+            let mut result = 0u64;
+            let mut shift = 0;
+            loop {
+                let byte = *self
+                    .buf
+                    .get(self.pos)
+                    .ok_or("Unexpected end of input while reading varint")?;
+                self.pos += 1;
+                result |= ((byte & 0x7f) as u64) << shift;
+                if byte & 0x80 == 0 {
+                    return Ok(result);
+                }
+                shift += 7;
+            }
+        }
+
+        fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+            let end = self
+                .pos
+                .checked_add(len)
+                .filter(|&end| end <= self.buf.len())
+                .ok_or("Length-delimited field runs past the end of input")?;
+            let bytes = &self.buf[self.pos..end];
+            self.pos = end;
+            Ok(bytes)
+        }
+
+        /// Reads one `(field_number, wire_type)` tag and its payload, skipping
+        /// whatever this reader doesn't otherwise know how to interpret.
+        fn read_field(&mut self) -> Result<(u64, Field<'a>), String> {
+            let tag = self.read_varint()?;
+            let field_number = tag >> 3;
+            match tag & 0x7 {
+                0 => Ok((field_number, Field::Varint(self.read_varint()?))),
+                1 => Ok((field_number, Field::Fixed64(self.read_bytes(8)?))),
+                2 => {
+                    let len = self.read_varint()? as usize;
+                    Ok((field_number, Field::LengthDelimited(self.read_bytes(len)?)))
+                }
+                5 => Ok((field_number, Field::Fixed32(self.read_bytes(4)?))),
+                other => Err(format!("Unsupported protobuf wire type {other}")),
+            }
+        }
+    }
+
+    enum Field<'a> {
+        Varint(u64),
+        Fixed64(&'a [u8]),
+        Fixed32(&'a [u8]),
+        LengthDelimited(&'a [u8]),
+    }
+
+    fn parse_piece(bytes: &[u8]) -> Result<Piece, String> {
+        let mut reader = Reader::new(bytes);
+        let mut piece = String::new();
+        let mut score = 0f32;
+        let mut kind = PieceType::Normal;
+        while !reader.eof() {
+            match reader.read_field()? {
+                (1, Field::LengthDelimited(s)) => {
+                    piece = String::from_utf8_lossy(s).into_owned()
+                }
+                (2, Field::Fixed32(bytes)) => {
+                    score = f32::from_le_bytes(bytes.try_into().unwrap())
+                }
+                (3, Field::Varint(v)) => kind = PieceType::from_i64(v as i64),
+                _ => {}
+            }
+        }
+        Ok(Piece { piece, score, kind })
+    }
+
+    fn parse_trainer_spec(bytes: &[u8]) -> Result<Option<i64>, String> {
+        let mut reader = Reader::new(bytes);
+        let mut unk_id = None;
+        while !reader.eof() {
+            // `unk_id` is field 40 of `TrainerSpec`.
+            if let (40, Field::Varint(v)) = reader.read_field()? {
+                unk_id = Some(v as i64);
+            }
+        }
+        Ok(unk_id)
+    }
+
+    /// Parses a whole `ModelProto` message: `pieces` is field 1 (repeated), and
+    /// `trainer_spec` is field 2.
+    pub(crate) fn parse(bytes: &[u8]) -> Result<ModelProto, String> {
+        let mut reader = Reader::new(bytes);
+        let mut pieces = Vec::new();
+        let mut trainer_spec_unk_id = None;
+        while !reader.eof() {
+            match reader.read_field()? {
+                (1, Field::LengthDelimited(piece_bytes)) => {
+                    pieces.push(parse_piece(piece_bytes)?)
+                }
+                (2, Field::LengthDelimited(spec_bytes)) => {
+                    trainer_spec_unk_id = parse_trainer_spec(spec_bytes)?
+                }
+                _ => {}
+            }
+        }
+        Ok(ModelProto {
+            pieces,
+            trainer_spec_unk_id,
+        })
+    }
+}
 
 /// Base class for all models
 ///
@@ -88,6 +253,20 @@ impl Model for PyModel {
     }
 }
 
+/// Reads and parses a SentencePiece `.model` file into its `ModelProto`.
+fn read_spm_model(path: &str) -> PyResult<spm::ModelProto> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        exceptions::PyException::new_err(format!(
+            "Error while reading SentencePiece model {path}: {e}"
+        ))
+    })?;
+    spm::parse(&bytes).map_err(|e| {
+        exceptions::PyException::new_err(format!(
+            "Error while parsing SentencePiece model {path}: {e}"
+        ))
+    })
+}
+
 impl<I> From<I> for PyModel
 where
     I: Into<ModelWrapper>,
@@ -201,7 +380,7 @@ impl PyModel {
         if name.is_some() {
             deprecation_warning(
                 py,
-                "0.10.0",
+                DeprecatedSince::unspecified("0.10.0"),
                 "Parameter `name` of Model.save has been renamed `prefix`",
             )?;
             if prefix.is_none() {
@@ -230,6 +409,81 @@ impl PyModel {
         PyTrainer::from(self.model.read().unwrap().get_trainer()).get_as_subtype(py)
     }
 
+    /// Instantiate a Model by auto-detecting its on-disk format
+    ///
+    /// Detection, in order: a SentencePiece `.model` protobuf becomes a
+    /// :class:`~tokenizers.models.Unigram`; a `tokenizer.json` (or any file whose
+    /// content is JSON with a `model` key) dispatches on its `model.type` field; a
+    /// `vocab.json` with a sibling `merges.txt` in the same directory becomes a
+    /// :class:`~tokenizers.models.BPE`; a bare `vocab.json` becomes a
+    /// :class:`~tokenizers.models.WordLevel`; and a newline-delimited `vocab.txt`
+    /// becomes a :class:`~tokenizers.models.WordPiece`.
+    ///
+    /// Args:
+    ///     path (:obj:`str`):
+    ///         The path to a model file in one of the supported formats
+    ///
+    /// Returns:
+    ///     :class:`~tokenizers.models.Model`: The loaded model, as its concrete subtype
+    #[staticmethod]
+    #[pyo3(text_signature = "(path)")]
+    fn from_file(py: Python, path: &str) -> PyResult<PyObject> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while reading model file {path}: {e}"))
+        })?;
+
+        if spm::parse(&bytes).is_ok_and(|proto| !proto.pieces.is_empty()) {
+            let unigram = PyUnigram::from_spm(&py.get_type::<PyUnigram>(), py, path)?;
+            return Ok(unigram.into_pyobject(py)?.into_any().into());
+        }
+
+        if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&bytes) {
+            if let Some(model_json) = json.get("model") {
+                let model: ModelWrapper = serde_json::from_value(model_json.clone())
+                    .map_err(|e| {
+                        exceptions::PyException::new_err(format!(
+                            "Error while parsing `model` from {path}: {e}"
+                        ))
+                    })?;
+                return PyModel::from(model).get_as_subtype(py);
+            }
+        }
+
+        let path = Path::new(path);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => {
+                let merges = path.with_file_name("merges.txt");
+                if merges.is_file() {
+                    let bpe = PyBPE::from_file(
+                        &py.get_type::<PyBPE>(),
+                        py,
+                        path.to_str().unwrap_or_default(),
+                        merges.to_str().unwrap_or_default(),
+                        None,
+                    )?;
+                    Ok(bpe.into_pyobject(py)?.into_any().into())
+                } else {
+                    let word_level = PyWordLevel::from_file(
+                        &py.get_type::<PyWordLevel>(),
+                        py,
+                        path.to_str().unwrap_or_default(),
+                        None,
+                    )?;
+                    Ok(word_level.into_pyobject(py)?.into_any().into())
+                }
+            }
+            _ => {
+                let word_piece = PyWordPiece::from_file(
+                    &py.get_type::<PyWordPiece>(),
+                    py,
+                    path.to_str().unwrap_or_default(),
+                    None,
+                )?;
+                Ok(word_piece.into_pyobject(py)?.into_any().into())
+            }
+        }
+    }
+
     fn __repr__(&self) -> PyResult<String> {
         crate::utils::serde_pyo3::repr(self)
             .map_err(|e| exceptions::PyException::new_err(e.to_string()))
@@ -319,6 +573,31 @@ impl PyBPE {
     }
 }
 
+/// The reversible byte <-> unicode table GPT-2-style byte-level BPE vocabularies use
+/// to store arbitrary bytes as valid (if not always printable) JSON string keys:
+/// printable bytes map to themselves and the rest of the 0-255 range maps to code
+/// points starting at U+0100, so every byte gets a unique, round-trippable
+/// representative.
+fn byte_level_char_map() -> Vec<(u8, char)> {
+    let mut bytes: Vec<u8> = (b'!'..=b'~')
+        .chain(0xA1u8..=0xACu8)
+        .chain(0xAEu8..=0xFFu8)
+        .collect();
+    let mut chars: Vec<u32> = bytes.iter().map(|&b| b as u32).collect();
+    let mut n = 0u32;
+    for b in 0u8..=255 {
+        if !bytes.contains(&b) {
+            bytes.push(b);
+            chars.push(256 + n);
+            n += 1;
+        }
+    }
+    bytes
+        .into_iter()
+        .zip(chars.into_iter().map(|c| char::from_u32(c).unwrap()))
+        .collect()
+}
+
 macro_rules! getter {
     ($self: ident, $variant: ident, $($name: tt)+) => {{
         let super_ = $self.as_ref();
@@ -457,7 +736,7 @@ impl PyBPE {
                 (PyVocab::Filename(vocab_filename), PyMerges::Filename(merges_filename)) => {
                     deprecation_warning(
                     py,
-                    "0.9.0",
+                    DeprecatedSince::unspecified("0.9.0"),
                     "BPE.__init__ will not create from files anymore, try `BPE.from_file` instead",
                 )?;
                     builder =
@@ -547,6 +826,174 @@ impl PyBPE {
         )
     }
 
+    /// Instantiate a BPE model from a SentencePiece `.model` protobuf trained with
+    /// the BPE model type.
+    ///
+    /// SentencePiece BPE pieces carry the same `(piece, score)` shape as Unigram but
+    /// no explicit merge list, so the merge table here is reconstructed: multi-
+    /// character `NORMAL` pieces (not control, user-defined or byte pieces) are
+    /// visited from highest to lowest score (SentencePiece
+    /// assigns higher scores to merges learned earlier) and each is split at the
+    /// first point that leaves both halves already present in the vocab built so
+    /// far, recovering the merge order the original BPE training most likely
+    /// produced. This is a best-effort reconstruction, not a byte-for-byte replay of
+    /// the original training.
+    ///
+    /// `byte_fallback` is turned on automatically when any `BYTE`-typed piece is
+    /// present, mirroring :meth:`~tokenizers.models.Unigram.from_spm`.
+    ///
+    /// Args:
+    ///     model_path (:obj:`str`):
+    ///         The path to a SentencePiece `.model` file
+    ///
+    /// Returns:
+    ///     :class:`~tokenizers.models.BPE`: An instance of BPE loaded from the
+    ///     SentencePiece model
+    #[classmethod]
+    #[pyo3(text_signature = "(cls, model_path)")]
+    fn from_spm(_cls: &Bound<'_, PyType>, py: Python, model_path: &str) -> PyResult<Py<Self>> {
+        let proto = read_spm_model(model_path)?;
+
+        let vocab: AHashMap<String, u32> = proto
+            .pieces
+            .iter()
+            .enumerate()
+            .map(|(id, piece)| (piece.piece.clone(), id as u32))
+            .collect();
+
+        let mut by_score: Vec<&spm::Piece> = proto
+            .pieces
+            .iter()
+            .filter(|p| p.kind == spm::PieceType::Normal && p.piece.chars().count() > 1)
+            .collect();
+        by_score
+            .sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut built: AHashSet<&str> = proto
+            .pieces
+            .iter()
+            .filter(|p| p.piece.chars().count() == 1)
+            .map(|p| p.piece.as_str())
+            .collect();
+        let mut merges: Merges = Vec::new();
+        for piece in by_score {
+            let chars: Vec<char> = piece.piece.chars().collect();
+            let split = (1..chars.len()).find(|&i| {
+                let left: String = chars[..i].iter().collect();
+                let right: String = chars[i..].iter().collect();
+                built.contains(left.as_str()) && built.contains(right.as_str())
+            });
+            if let Some(i) = split {
+                let left: String = chars[..i].iter().collect();
+                let right: String = chars[i..].iter().collect();
+                merges.push((left, right));
+            }
+            built.insert(&piece.piece);
+        }
+
+        let byte_fallback = proto.pieces.iter().any(|p| p.kind == spm::PieceType::Byte);
+        let builder = BPE::builder().vocab_and_merges(vocab, merges);
+        let kwargs = if byte_fallback {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("byte_fallback", true)?;
+            Some(kwargs)
+        } else {
+            None
+        };
+        let (bpe, model) = PyBPE::with_builder(builder, kwargs.as_ref())?;
+        Py::new(py, (bpe, model))
+    }
+
+    /// The canonical byte -> unicode table used by GPT-2-style byte-level BPE vocabularies
+    ///
+    /// Returns:
+    ///     :obj:`Dict[int, str]`: Each byte 0-255 mapped to its printable unicode representative
+    #[staticmethod]
+    #[pyo3(text_signature = "()")]
+    fn bytes_to_char_map() -> HashMap<u8, char> {
+        byte_level_char_map().into_iter().collect()
+    }
+
+    /// The inverse of :meth:`~tokenizers.models.BPE.bytes_to_char_map`
+    ///
+    /// Returns:
+    ///     :obj:`Dict[str, int]`: Each printable unicode representative mapped back to its byte
+    #[staticmethod]
+    #[pyo3(text_signature = "()")]
+    fn char_to_bytes_map() -> HashMap<char, u8> {
+        byte_level_char_map().into_iter().map(|(b, c)| (c, b)).collect()
+    }
+
+    /// Instantiate a BPE model from a byte-level vocab/merges pair
+    ///
+    /// `vocab` and `merges` are ordinary GPT-2-style byte-level BPE files, where every
+    /// token has already been passed through :meth:`~tokenizers.models.BPE.bytes_to_char_map`
+    /// so that it's a valid (if not always printable) unicode string. This decodes each
+    /// token back through :meth:`~tokenizers.models.BPE.char_to_bytes_map` into the raw
+    /// bytes it represents, so that tokens whose bytes happen to form valid UTF-8 (most
+    /// multi-byte tokens) end up as ordinary text instead of the mapped-byte
+    /// representation. A canonical byte-level vocabulary also contains the 256 base
+    /// single-byte tokens, and half of those (bytes 0x80-0xFF) aren't valid UTF-8 on
+    /// their own (e.g. a lone continuation byte); those are left in their original
+    /// byte-mapped form rather than failing the whole load. Use this instead of
+    /// :meth:`~tokenizers.models.BPE.from_file` when the vocabulary isn't meant to be
+    /// paired with a `ByteLevel` pre-tokenizer.
+    ///
+    /// Args:
+    ///     vocab (:obj:`str`):
+    ///         The path to a byte-level :obj:`vocab.json` file
+    ///
+    ///     merges (:obj:`str`):
+    ///         The path to a byte-level :obj:`merges.txt` file
+    ///
+    /// Returns:
+    ///     :class:`~tokenizers.models.BPE`: An instance of BPE with the decoded vocabulary
+    #[classmethod]
+    #[pyo3(text_signature = "(cls, vocab, merges)")]
+    fn from_byte_level_file(
+        _cls: &Bound<'_, PyType>,
+        py: Python,
+        vocab: &str,
+        merges: &str,
+    ) -> PyResult<Py<Self>> {
+        let (vocab, merges) = BPE::read_file(vocab, merges).map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while reading BPE files: {e}"))
+        })?;
+
+        let char_to_byte: AHashMap<char, u8> =
+            byte_level_char_map().into_iter().map(|(b, c)| (c, b)).collect();
+        // A token's bytes don't always form valid UTF-8 on their own (most of the 256
+        // base single-byte tokens, 0x80-0xFF, don't), so this can't unconditionally decode: tokens
+        // that fail are kept in their original byte-mapped form instead of aborting
+        // the whole load.
+        let decode = |token: &str| -> PyResult<String> {
+            let bytes = token
+                .chars()
+                .map(|c| {
+                    char_to_byte.get(&c).copied().ok_or_else(|| {
+                        exceptions::PyValueError::new_err(format!(
+                            "Token {token:?} contains a character outside the byte-level mapping"
+                        ))
+                    })
+                })
+                .collect::<PyResult<Vec<u8>>>()?;
+            Ok(String::from_utf8(bytes).unwrap_or_else(|_| token.to_owned()))
+        };
+
+        let vocab: AHashMap<String, u32> = vocab
+            .into_iter()
+            .map(|(token, id)| Ok((decode(&token)?, id)))
+            .collect::<PyResult<_>>()?;
+        let merges: Merges = merges
+            .into_iter()
+            .map(|(a, b)| Ok((decode(&a)?, decode(&b)?)))
+            .collect::<PyResult<_>>()?;
+
+        let builder = BPE::builder().vocab_and_merges(vocab, merges);
+        let (bpe, model) = PyBPE::with_builder(builder, None)?;
+        Py::new(py, (bpe, model))
+    }
+
     /// Clears the internal cache
     #[pyo3(signature = ())]
     #[pyo3(text_signature = "(self)")]
@@ -570,6 +1017,165 @@ impl PyBPE {
         model.resize_cache(capacity);
         Ok(())
     }
+
+    /// Get the number of entries currently held in the internal cache
+    #[pyo3(signature = ())]
+    #[pyo3(text_signature = "(self)")]
+    fn get_cache_size(self_: PyRef<Self>) -> PyResult<usize> {
+        let super_ = self_.as_ref();
+        let model = super_.model.read().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while reading BPE cache: {e}"))
+        })?;
+        Ok(model.get_cache_size())
+    }
+
+    /// Get the current merge table
+    ///
+    /// Returns:
+    ///     :obj:`List[Tuple[str, str]]`: The `(a, b)` merge pairs, in rank order
+    #[pyo3(signature = ())]
+    #[pyo3(text_signature = "(self)")]
+    fn get_merges(self_: PyRef<Self>) -> Vec<(String, String)> {
+        getter!(self_, BPE, get_merges())
+    }
+
+    /// Append new merges after the existing ones
+    ///
+    /// This is meant for continued/domain-adaptive training: splice extra merges
+    /// learned from a new corpus into an already-built model without rebuilding it
+    /// from scratch. Both symbols of every merge must already be in the vocabulary
+    /// (add them first with :meth:`~tokenizers.models.BPE.add_tokens_to_vocab` if
+    /// needed); adding merges invalidates the tokenization cache.
+    ///
+    /// Args:
+    ///     merges (:obj:`List[Tuple[str, str]]`):
+    ///         The `(a, b)` merge pairs to add, in the order they should take effect
+    #[pyo3(text_signature = "(self, merges)")]
+    fn add_merges(self_: PyRef<Self>, merges: Merges) -> PyResult<()> {
+        let super_ = self_.as_ref();
+        let mut model = super_.model.write().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while adding BPE merges: {e}"))
+        })?;
+        if let ModelWrapper::BPE(ref mut bpe) = *model {
+            bpe.add_merges(&merges).map_err(|e| {
+                exceptions::PyException::new_err(format!("Error while adding BPE merges: {e}"))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Register new vocabulary entries with fresh ids
+    ///
+    /// Typically used to register the symbols produced by merges just added with
+    /// :meth:`~tokenizers.models.BPE.add_merges`.
+    ///
+    /// Args:
+    ///     vocab (:obj:`Dict[str, int]`):
+    ///         The token -> id entries to add
+    #[pyo3(text_signature = "(self, vocab)")]
+    fn add_tokens_to_vocab(self_: PyRef<Self>, vocab: HashMap<String, u32>) -> PyResult<()> {
+        let super_ = self_.as_ref();
+        let mut model = super_.model.write().map_err(|e| {
+            exceptions::PyException::new_err(format!(
+                "Error while adding tokens to BPE vocab: {e}"
+            ))
+        })?;
+        if let ModelWrapper::BPE(ref mut bpe) = *model {
+            bpe.add_tokens_to_vocab(vocab.into_iter().collect());
+        }
+        Ok(())
+    }
+
+    /// Tokenize a list of frequent whole-words purely to populate the merge cache
+    ///
+    /// Useful right after loading a model, so the hottest vocabulary is already
+    /// warm before the first real request instead of paying for cold merges on it.
+    ///
+    /// Args:
+    ///     words (:obj:`List[str]`):
+    ///         The words to pre-tokenize into the cache
+    #[pyo3(text_signature = "(self, words)")]
+    fn warm_cache(self_: PyRef<Self>, words: Vec<String>) -> PyResult<()> {
+        let super_ = self_.as_ref();
+        let model = super_.model.read().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while warming BPE cache: {e}"))
+        })?;
+        for word in &words {
+            ToPyResult(model.tokenize(word).map(|_| ())).into_py()?;
+        }
+        Ok(())
+    }
+
+    /// Dump the internal tokenization cache to a file
+    ///
+    /// The cache is stored as a JSON map from input word to the sequence of
+    /// resulting token strings, so a service can persist it across restarts.
+    ///
+    /// Args:
+    ///     path (:obj:`str`):
+    ///         The path of the file to write the cache to
+    #[pyo3(text_signature = "(self, path)")]
+    fn _dump_cache(self_: PyRef<Self>, path: &str) -> PyResult<()> {
+        let super_ = self_.as_ref();
+        let model = super_.model.read().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while dumping BPE cache: {e}"))
+        })?;
+        if let ModelWrapper::BPE(ref bpe) = *model {
+            let data = serde_json::to_vec(&bpe.get_cache()).map_err(|e| {
+                exceptions::PyException::new_err(format!(
+                    "Error while serializing BPE cache: {e}"
+                ))
+            })?;
+            std::fs::write(path, data).map_err(|e| {
+                exceptions::PyException::new_err(format!(
+                    "Error while writing BPE cache to {path}: {e}"
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Load a previously dumped tokenization cache from a file
+    ///
+    /// Entries are validated against the current merges before being kept: any
+    /// entry whose word would now retokenize differently (e.g. the cache was
+    /// dumped against an older merge table) is dropped instead of silently
+    /// corrupting output.
+    ///
+    /// Args:
+    ///     path (:obj:`str`):
+    ///         The path of a cache file previously written by `_dump_cache`
+    #[pyo3(text_signature = "(self, path)")]
+    fn _load_cache(self_: PyRef<Self>, path: &str) -> PyResult<()> {
+        let super_ = self_.as_ref();
+        let mut model = super_.model.write().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while loading BPE cache: {e}"))
+        })?;
+        if let ModelWrapper::BPE(ref mut bpe) = *model {
+            let data = std::fs::read(path).map_err(|e| {
+                exceptions::PyException::new_err(format!(
+                    "Error while reading BPE cache from {path}: {e}"
+                ))
+            })?;
+            let cache: HashMap<String, Vec<String>> =
+                serde_json::from_slice(&data).map_err(|e| {
+                    exceptions::PyException::new_err(format!(
+                        "Error while deserializing BPE cache: {e}"
+                    ))
+                })?;
+
+            let valid: HashMap<String, Vec<String>> = cache
+                .into_iter()
+                .filter(|(word, tokens)| {
+                    bpe.tokenize(word)
+                        .map(|current| current.iter().map(|t| &t.value).eq(tokens.iter()))
+                        .unwrap_or(false)
+                })
+                .collect();
+            bpe.set_cache(valid);
+        }
+        Ok(())
+    }
 }
 
 /// An implementation of the WordPiece algorithm
@@ -583,6 +1189,9 @@ impl PyBPE {
 ///
 ///     max_input_chars_per_word (:obj:`int`, `optional`):
 ///         The maximum number of characters to authorize in a single word.
+///
+///     byte_fallback (:obj:`bool`, `optional`):
+///         Whether to use spm byte-fallback trick (defaults to False)
 #[pyclass(extends=PyModel, module = "tokenizers.models", name = "WordPiece")]
 pub struct PyWordPiece {}
 
@@ -604,6 +1213,7 @@ impl PyWordPiece {
                     "continuing_subword_prefix" => {
                         builder = builder.continuing_subword_prefix(val.extract()?);
                     }
+                    "byte_fallback" => builder = builder.byte_fallback(val.extract()?),
                     _ => println!("Ignored unknown kwargs option {key}"),
                 }
             }
@@ -655,8 +1265,18 @@ impl PyWordPiece {
         setter!(self_, WordPiece, max_input_chars_per_word, max);
     }
 
+    #[getter]
+    fn get_byte_fallback(self_: PyRef<Self>) -> bool {
+        getter!(self_, WordPiece, byte_fallback)
+    }
+
+    #[setter]
+    fn set_byte_fallback(self_: PyRef<Self>, byte_fallback: bool) {
+        setter!(self_, WordPiece, byte_fallback, byte_fallback);
+    }
+
     #[new]
-    #[pyo3(signature = (vocab=None, **kwargs), text_signature = "(self, vocab, unk_token, max_input_chars_per_word)")]
+    #[pyo3(signature = (vocab=None, **kwargs), text_signature = "(self, vocab, unk_token, max_input_chars_per_word, byte_fallback)")]
     fn new(
         py: Python<'_>,
         vocab: Option<PyVocab>,
@@ -673,7 +1293,7 @@ impl PyWordPiece {
                 PyVocab::Filename(vocab_filename) => {
                     deprecation_warning(
                         py,
-                        "0.9.0",
+                        DeprecatedSince::unspecified("0.9.0"),
                         "WordPiece.__init__ will not create from files anymore, try `WordPiece.from_file` instead",
                     )?;
                     builder = builder.files(vocab_filename.to_string());
@@ -741,6 +1361,41 @@ impl PyWordPiece {
             PyWordPiece::new(py, Some(PyVocab::Vocab(vocab)), kwargs)?,
         )
     }
+
+    /// Clears the internal cache
+    #[pyo3(signature = ())]
+    #[pyo3(text_signature = "(self)")]
+    fn _clear_cache(self_: PyRef<Self>) -> PyResult<()> {
+        let super_ = self_.as_ref();
+        let mut model = super_.model.write().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while clearing WordPiece cache: {e}"))
+        })?;
+        model.clear_cache();
+        Ok(())
+    }
+
+    /// Resize the internal cache
+    #[pyo3(signature = (capacity))]
+    #[pyo3(text_signature = "(self, capacity)")]
+    fn _resize_cache(self_: PyRef<Self>, capacity: usize) -> PyResult<()> {
+        let super_ = self_.as_ref();
+        let mut model = super_.model.write().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while resizing WordPiece cache: {e}"))
+        })?;
+        model.resize_cache(capacity);
+        Ok(())
+    }
+
+    /// Get the number of entries currently held in the internal cache
+    #[pyo3(signature = ())]
+    #[pyo3(text_signature = "(self)")]
+    fn get_cache_size(self_: PyRef<Self>) -> PyResult<usize> {
+        let super_ = self_.as_ref();
+        let model = super_.model.read().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while reading WordPiece cache: {e}"))
+        })?;
+        Ok(model.get_cache_size())
+    }
 }
 
 /// An implementation of the WordLevel algorithm
@@ -786,7 +1441,7 @@ impl PyWordLevel {
                 PyVocab::Filename(vocab_filename) => {
                     deprecation_warning(
                         py,
-                        "0.9.0",
+                        DeprecatedSince::unspecified("0.9.0"),
                         "WordLevel.__init__ will not create from files anymore, \
                             try `WordLevel.from_file` instead",
                     )?;
@@ -900,6 +1555,142 @@ impl PyUnigram {
         }
     }
 
+    /// Instantiate a Unigram model from a SentencePiece `.model` protobuf
+    ///
+    /// This reads the `pieces` directly out of the SentencePiece `ModelProto`, with
+    /// no external SentencePiece dependency needed: each piece's `(text, score)`
+    /// becomes a vocab entry in order, the piece typed `UNKNOWN` becomes the model's
+    /// `unk_id` (falling back to the model's `trainer_spec.unk_id` if no piece is so
+    /// typed), and `byte_fallback` is turned on automatically when any `BYTE`-typed
+    /// piece is present. The `▁` (U+2581) word-boundary marker used by SentencePiece
+    /// is kept verbatim.
+    ///
+    /// Args:
+    ///     model_path (:obj:`str`):
+    ///         The path to a SentencePiece `.model` file
+    ///
+    /// Returns:
+    ///     :class:`~tokenizers.models.Unigram`: An instance of Unigram loaded from
+    ///     the SentencePiece model
+    #[classmethod]
+    #[pyo3(text_signature = "(cls, model_path)")]
+    fn from_spm(_cls: &Bound<'_, PyType>, py: Python, model_path: &str) -> PyResult<Py<Self>> {
+        let proto = read_spm_model(model_path)?;
+
+        let vocab: Vec<(String, f64)> = proto
+            .pieces
+            .iter()
+            .map(|p| (p.piece.clone(), p.score as f64))
+            .collect();
+        // `trainer_spec.unk_id` is only trusted as a fallback (and only once it's
+        // range-checked): a malformed or truncated `.model` file could otherwise
+        // hand us an index past the end of `pieces`.
+        let unk_id = proto
+            .pieces
+            .iter()
+            .position(|p| p.kind == spm::PieceType::Unknown)
+            .or_else(|| {
+                proto
+                    .trainer_spec_unk_id
+                    .and_then(|id| usize::try_from(id).ok())
+            })
+            .filter(|&id| id < proto.pieces.len());
+        let byte_fallback = proto.pieces.iter().any(|p| p.kind == spm::PieceType::Byte);
+
+        Py::new(py, PyUnigram::new(Some(vocab), unk_id, Some(byte_fallback))?)
+    }
+
+    /// Instantiate a Unigram model from a SentencePiece `.model` protobuf, optionally
+    /// overriding its piece -> id mapping with a separate `vocab.json`
+    ///
+    /// Some published tokenizers (e.g. Speech2Text-style models) ship a SentencePiece
+    /// model purely for segmentation alongside a `vocab.json` that renumbers and
+    /// filters the effective vocabulary. When `vocab` is given, only the pieces it
+    /// names are kept, ordered by the ids it assigns rather than by their order in
+    /// the SentencePiece model, since a piece's position in the resulting
+    /// vocabulary *is* its id: every named piece must be one `spm_file` actually
+    /// has, and the ids as a whole must form a dense `0..n` range, or this raises
+    /// a clear error instead of building a model with silently wrong ids. Without
+    /// `vocab`, this is equivalent to :meth:`~tokenizers.models.Unigram.from_spm`.
+    ///
+    /// Args:
+    ///     spm_file (:obj:`str`):
+    ///         The path to a SentencePiece `.model` file
+    ///
+    ///     vocab (:obj:`str`, `optional`):
+    ///         The path to a `vocab.json` overriding the piece -> id mapping
+    ///
+    /// Returns:
+    ///     :class:`~tokenizers.models.Unigram`: An instance of Unigram loaded from
+    ///     the SentencePiece model
+    #[classmethod]
+    #[pyo3(signature = (spm_file, vocab=None))]
+    #[pyo3(text_signature = "(cls, spm_file, vocab=None)")]
+    fn from_file(
+        cls: &Bound<'_, PyType>,
+        py: Python,
+        spm_file: &str,
+        vocab: Option<&str>,
+    ) -> PyResult<Py<Self>> {
+        let vocab_path = match vocab {
+            Some(vocab_path) => vocab_path,
+            None => return PyUnigram::from_spm(cls, py, spm_file),
+        };
+
+        let proto = read_spm_model(spm_file)?;
+        let scores: AHashMap<&str, f64> = proto
+            .pieces
+            .iter()
+            .map(|p| (p.piece.as_str(), p.score as f64))
+            .collect();
+
+        let data = std::fs::read(vocab_path).map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while reading {vocab_path}: {e}"))
+        })?;
+        let overrides: HashMap<String, u32> = serde_json::from_slice(&data).map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while parsing {vocab_path}: {e}"))
+        })?;
+
+        let mut by_id: Vec<(u32, String, f64)> = overrides
+            .into_iter()
+            .map(|(piece, id)| {
+                let score = scores.get(piece.as_str()).copied().ok_or_else(|| {
+                    exceptions::PyValueError::new_err(format!(
+                        "{vocab_path} assigns id {id} to piece {piece:?}, which isn't in \
+                         {spm_file}"
+                    ))
+                })?;
+                Ok((id, piece, score))
+            })
+            .collect::<PyResult<_>>()?;
+        by_id.sort_by_key(|(id, _, _)| *id);
+
+        for (expected, (id, piece, _)) in by_id.iter().enumerate() {
+            if *id != expected as u32 {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "{vocab_path} doesn't assign a dense 0..n id range: piece {piece:?} has id \
+                     {id}, expected {expected}"
+                )));
+            }
+        }
+
+        let vocab: Vec<(String, f64)> = by_id
+            .into_iter()
+            .map(|(_, piece, score)| (piece, score))
+            .collect();
+        let unk_id = proto
+            .pieces
+            .iter()
+            .position(|p| p.kind == spm::PieceType::Unknown)
+            .and_then(|spm_id| {
+                let unk_piece = &proto.pieces[spm_id].piece;
+                vocab.iter().position(|(piece, _)| piece == unk_piece)
+            });
+        let byte_fallback = proto.pieces.iter().any(|p| p.kind == spm::PieceType::Byte);
+
+        Py::new(py, PyUnigram::new(Some(vocab), unk_id, Some(byte_fallback))?)
+    }
+
     /// Clears the internal cache
     #[pyo3(signature = ())]
     #[pyo3(text_signature = "(self)")]
@@ -923,6 +1714,17 @@ impl PyUnigram {
         model.resize_cache(capacity);
         Ok(())
     }
+
+    /// Get the number of entries currently held in the internal cache
+    #[pyo3(signature = ())]
+    #[pyo3(text_signature = "(self)")]
+    fn get_cache_size(self_: PyRef<Self>) -> PyResult<usize> {
+        let super_ = self_.as_ref();
+        let model = super_.model.read().map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while reading Unigram cache: {e}"))
+        })?;
+        Ok(model.get_cache_size())
+    }
 }
 
 /// Models Module
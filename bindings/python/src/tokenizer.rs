@@ -0,0 +1,99 @@
+use pyo3::prelude::*;
+use tk::tokenizer::Tokenizer;
+use tokenizers as tk;
+
+use crate::encoding::PyEncoding;
+use crate::error::{into_exception_group, ToPyResult};
+
+/// A `Tokenizer` works as a pipeline. It processes some raw text as input
+/// and outputs an `Encoding`.
+#[pyclass(dict, module = "tokenizers", name = "Tokenizer")]
+#[derive(Clone)]
+pub struct PyTokenizer {
+    pub(crate) tokenizer: Tokenizer,
+}
+
+impl PyTokenizer {
+    /// Runs every input through the tokenizer regardless of earlier failures, then
+    /// either raises the first error (matching the long-standing fail-fast behavior)
+    /// or, when `raise_exception_group` opts in, aggregates every `(index, error)`
+    /// into a single PEP 654 `ExceptionGroup` via [`into_exception_group`] so one
+    /// `except*` can see every failing input from one call.
+    fn encode_batch_impl(
+        &self,
+        py: Python<'_>,
+        input: Vec<String>,
+        add_special_tokens: bool,
+        raise_exception_group: bool,
+        fast: bool,
+    ) -> PyResult<Vec<PyEncoding>> {
+        let mut encodings = Vec::with_capacity(input.len());
+        let mut failures = Vec::new();
+        for (index, text) in input.into_iter().enumerate() {
+            let result = if fast {
+                self.tokenizer.encode_fast(text.as_str(), add_special_tokens)
+            } else {
+                self.tokenizer.encode(text.as_str(), add_special_tokens)
+            };
+            match result {
+                Ok(encoding) => encodings.push(PyEncoding::from(encoding)),
+                Err(err) => failures.push((index, err)),
+            }
+        }
+
+        if failures.is_empty() {
+            return Ok(encodings);
+        }
+        if raise_exception_group {
+            return Err(into_exception_group(py, failures)?);
+        }
+        let (_, first_error) = failures.into_iter().next().unwrap();
+        ToPyResult(Err(first_error)).into_py()
+    }
+}
+
+#[pymethods]
+impl PyTokenizer {
+    /// Encode the given batch of inputs.
+    ///
+    /// Args:
+    ///     input (A `List` of `str`):
+    ///         A list of sequences to encode
+    ///
+    ///     add_special_tokens (`bool`, defaults to `True`):
+    ///         Whether to add the special tokens
+    ///
+    ///     raise_exception_group (`bool`, defaults to `False`):
+    ///         Opt-in aggregating mode: instead of raising on the first input that
+    ///         fails to encode, run the whole batch and raise a single
+    ///         `ExceptionGroup` holding every failure, each annotated with the index
+    ///         of the input that produced it.
+    ///
+    /// Returns:
+    ///     A `List` of `Encoding`: The encoded batch
+    #[pyo3(signature = (input, add_special_tokens = true, raise_exception_group = false))]
+    fn encode_batch(
+        &self,
+        py: Python<'_>,
+        input: Vec<String>,
+        add_special_tokens: bool,
+        raise_exception_group: bool,
+    ) -> PyResult<Vec<PyEncoding>> {
+        self.encode_batch_impl(py, input, add_special_tokens, raise_exception_group, false)
+    }
+
+    /// Encode the given batch of inputs without word/char alignment tracking, for
+    /// callers who don't need it. Faster than `encode_batch`.
+    ///
+    /// See `encode_batch` for a description of `raise_exception_group`.
+    #[pyo3(signature = (input, add_special_tokens = true, raise_exception_group = false))]
+    fn encode_batch_fast(
+        &self,
+        py: Python<'_>,
+        input: Vec<String>,
+        add_special_tokens: bool,
+        raise_exception_group: bool,
+    ) -> PyResult<Vec<PyEncoding>> {
+        self.encode_batch_impl(py, input, add_special_tokens, raise_exception_group, true)
+    }
+}
@@ -49,6 +49,12 @@
 //!
 //! The same construct is used for special tokens: `<identifier>(:<type_id>)?`.
 //!
+//! **Note on arities beyond `A`/`B`**: a bare number after `$` (`$0`, `$1`, `$2`, ...) is
+//! kept, for backward compatibility, as the `type_id`-on-sequence-`A` shorthand above, *not*
+//! as a sequence index. To reference the third input sequence and beyond (for templates built
+//! with [`TemplateProcessingBuilder::try_multi`]/`try_nseq`), use a letter past `B` (`$C`,
+//! `$D`, ...) or the explicit `$seq<N>` form (`$seq2` is the same sequence as `$C`).
+//!
 //! **Warning**: You must ensure that you are giving the correct tokens/ids as these will
 //! be added to the `Encoding` without any further check. If the given ids correspond to
 //! something totally different in a `Tokenizer` using this `PostProcessor`, it might lead
@@ -63,13 +69,84 @@ use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use std::result::Result as StdResult;
 
-/// Represents any sequences received as input of the PostProcessor
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Eq)]
-pub enum Sequence {
+/// Represents any of the sequences received as input of the PostProcessor, identified
+/// by its 0-based index among the input encodings. `A` and `B` are the common two-input
+/// case, kept as named constants for readability and for backward-compatible
+/// `$A`/`$B`/`"A"`/`"B"` DSL and JSON representations; templates with three or more
+/// input sequences reference the extra ones with a letter past `B` (`$C`, `$D`, ...) or
+/// the explicit `$seq<N>` form — *not* a bare digit, which is reserved for the
+/// pre-existing `type_id`-on-sequence-`A` shorthand (`$2` means "sequence `A`,
+/// `type_id` 2", not "sequence 2").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sequence(usize);
+
+impl Sequence {
     /// This is the first sequence, the one that is always specified
-    A,
+    #[allow(non_upper_case_globals)]
+    pub const A: Self = Self(0);
     /// This is the pair sequence, that is optional
-    B,
+    #[allow(non_upper_case_globals)]
+    pub const B: Self = Self(1);
+
+    /// Build a `Sequence` from its 0-based index among the input encodings
+    fn from_index(index: usize) -> Self {
+        Self(index)
+    }
+
+    /// The 0-based index of this sequence among the input encodings
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl Serialize for Sequence {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.0 {
+            0 => serializer.serialize_str("A"),
+            1 => serializer.serialize_str("B"),
+            n => serializer.serialize_u64(n as u64),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Sequence {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Name(String),
+            Index(usize),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Name(name) if name == "A" => Ok(Self::A),
+            Repr::Name(name) if name == "B" => Ok(Self::B),
+            Repr::Name(name) => Err(serde::de::Error::custom(format!(
+                "Unknown Sequence variant \"{name}\", expected \"A\", \"B\" or an index"
+            ))),
+            Repr::Index(index) => Ok(Self::from_index(index)),
+        }
+    }
+}
+
+impl std::fmt::Display for Sequence {
+    /// Renders the bare sequence designator used in the DSL, without the `$` sigil
+    /// (e.g. `A`, `B`, `C`, ..., `seq26`): the inverse of the letter/`seq<N>` parsing
+    /// done in [`Piece::extract_id`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => write!(f, "A"),
+            1 => write!(f, "B"),
+            n if n < 26 => write!(f, "{}", (b'A' + n as u8) as char),
+            n => write!(f, "seq{n}"),
+        }
+    }
 }
 
 /// Represents the different kind of pieces that constitute a template.
@@ -94,8 +171,30 @@ pub enum Sequence {
 ///
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Eq)]
 pub enum Piece {
-    Sequence { id: Sequence, type_id: u32 },
-    SpecialToken { id: String, type_id: u32 },
+    Sequence {
+        id: Sequence,
+        type_id: u32,
+        /// Only emit this piece when `condition` evaluates to true against the input
+        /// encodings. `None` means the piece is always emitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        condition: Option<Condition>,
+    },
+    SpecialToken {
+        id: String,
+        type_id: u32,
+        /// Only emit this piece when `condition` evaluates to true against the input
+        /// encodings. `None` means the piece is always emitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        condition: Option<Condition>,
+    },
+    /// A group of pieces that are all skipped together when `when`'s input encoding
+    /// is empty (or absent). Unlike `condition`, which guards a single piece, this
+    /// lets e.g. a separator and the sequence it separates drop out together instead
+    /// of requiring the same guard repeated on every piece in the group.
+    Optional {
+        when: Sequence,
+        pieces: Vec<Piece>,
+    },
 }
 
 impl Piece {
@@ -108,20 +207,45 @@ impl Piece {
                 "" => Some(Self::Sequence {
                     id: Sequence::A,
                     type_id: 0,
+                    condition: None,
                 }),
                 "A" | "a" => Some(Self::Sequence {
                     id: Sequence::A,
                     type_id: 0,
+                    condition: None,
                 }),
                 "B" | "b" => Some(Self::Sequence {
                     id: Sequence::B,
                     type_id: 0,
+                    condition: None,
                 }),
+                // `$seq<N>` explicitly designates the sequence at index `N`, for templates
+                // that reference more than the usual A/B pair.
+                n if n.starts_with("seq") => {
+                    let index: usize = n.strip_prefix("seq")?.parse().ok()?;
+                    Some(Self::Sequence {
+                        id: Sequence::from_index(index),
+                        type_id: 0,
+                        condition: None,
+                    })
+                }
+                // A single letter beyond `A`/`B` designates the sequence at the matching
+                // index: `$C` is sequence 2, `$D` is sequence 3, etc.
+                n if n.len() == 1 && n.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) => {
+                    let c = n.chars().next().unwrap().to_ascii_uppercase();
+                    let index = (c as usize) - ('A' as usize);
+                    Some(Self::Sequence {
+                        id: Sequence::from_index(index),
+                        type_id: 0,
+                        condition: None,
+                    })
+                }
                 n => {
                     if let Ok(type_id) = n.parse::<u32>() {
                         Some(Self::Sequence {
                             id: Sequence::A,
                             type_id,
+                            condition: None,
                         })
                     } else {
                         None
@@ -132,14 +256,406 @@ impl Piece {
             Some(Self::SpecialToken {
                 id: s.to_owned(),
                 type_id: 0,
+                condition: None,
             })
         }
     }
 
     fn with_type_id(self, type_id: u32) -> Self {
         match self {
-            Self::Sequence { id, .. } => Self::Sequence { id, type_id },
-            Self::SpecialToken { id, .. } => Self::SpecialToken { id, type_id },
+            Self::Sequence { id, condition, .. } => Self::Sequence {
+                id,
+                type_id,
+                condition,
+            },
+            Self::SpecialToken { id, condition, .. } => Self::SpecialToken {
+                id,
+                type_id,
+                condition,
+            },
+            Self::Optional { .. } => self,
+        }
+    }
+
+    fn with_condition(self, condition: Option<Condition>) -> Self {
+        match self {
+            Self::Sequence { id, type_id, .. } => Self::Sequence {
+                id,
+                type_id,
+                condition,
+            },
+            Self::SpecialToken { id, type_id, .. } => Self::SpecialToken {
+                id,
+                type_id,
+                condition,
+            },
+            Self::Optional { .. } => self,
+        }
+    }
+
+    /// The guard, if any, that decides whether this piece is emitted. `Optional`
+    /// pieces are guarded by their `when` sequence instead, via [`Piece::is_skipped`].
+    fn condition(&self) -> Option<&Condition> {
+        match self {
+            Self::Sequence { condition, .. } => condition.as_ref(),
+            Self::SpecialToken { condition, .. } => condition.as_ref(),
+            Self::Optional { .. } => None,
+        }
+    }
+
+    /// Whether this piece should be skipped entirely given the actual input
+    /// `encodings`: either its `condition` evaluates to false, or (for `Optional`) the
+    /// `when` sequence's encoding is missing or has zero tokens.
+    fn is_skipped(&self, encodings: &[Encoding]) -> bool {
+        match self {
+            Self::Optional { when, .. } => {
+                !encodings.get(when.index()).is_some_and(|e| !e.is_empty())
+            }
+            _ => self.condition().is_some_and(|c| !c.evaluate(encodings)),
+        }
+    }
+}
+
+/// A boolean predicate evaluated against the set of input sequences, used to guard
+/// whether a [`Piece`] is emitted. This lets a single template cover cases (single,
+/// pair, or any input being empty) that would otherwise require separate templates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Eq)]
+pub enum Condition {
+    /// True if the given sequence was supplied as input at all
+    HasSequence(Sequence),
+    /// True if the given sequence was supplied and contains at least one token
+    SequenceNonEmpty(Sequence),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    fn evaluate(&self, encodings: &[Encoding]) -> bool {
+        match self {
+            Self::HasSequence(seq) => encodings.get(seq.index()).is_some(),
+            Self::SequenceNonEmpty(seq) => {
+                encodings.get(seq.index()).is_some_and(|e| !e.is_empty())
+            }
+            Self::And(conditions) => conditions.iter().all(|c| c.evaluate(encodings)),
+            Self::Or(conditions) => conditions.iter().any(|c| c.evaluate(encodings)),
+            Self::Not(condition) => !condition.evaluate(encodings),
+        }
+    }
+}
+
+impl Condition {
+    /// Whether this condition is a compound (`And`/`Or`). Used by [`fmt_operand`] to
+    /// decide when a nested condition needs parenthesizing: without it, a compound
+    /// nested inside another `And`/`Or`/`Not` would flatten into its parent's `&`/`|`
+    /// join and change meaning (or become ambiguous) once rendered back out.
+    ///
+    /// [`fmt_operand`]: Condition::fmt_operand
+    fn is_compound(&self) -> bool {
+        matches!(self, Self::And(_) | Self::Or(_))
+    }
+
+    /// Renders this condition as an operand of a parent `And`/`Or`/`Not`, parenthesizing
+    /// it first if it's itself a compound so [`parse_condition`] recovers the tree
+    /// `Display` produced, however deeply nested.
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_compound() {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    /// Renders the condition suffix (the part following `?` in a piece specification)
+    /// accepted by [`parse_condition`], the inverse of that parser.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HasSequence(seq) => write!(f, "${seq}"),
+            Self::SequenceNonEmpty(seq) => write!(f, "${seq}+"),
+            Self::Not(condition) => {
+                write!(f, "!")?;
+                condition.fmt_operand(f)
+            }
+            Self::And(conditions) => {
+                for (i, c) in conditions.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "&")?;
+                    }
+                    c.fmt_operand(f)?;
+                }
+                Ok(())
+            }
+            Self::Or(conditions) => {
+                for (i, c) in conditions.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "|")?;
+                    }
+                    c.fmt_operand(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parse a condition suffix (the part following `?` in a piece specification) into a
+/// [`Condition`] tree: a recursive-descent parser over
+/// `or := and ('|' and)*`, `and := atom ('&' atom)*`,
+/// `atom := '!' atom | '(' or ')' | '$'<sequence>('+')?`,
+/// with `&` binding tighter than `|`, same as [`Condition`]'s `Display`, which
+/// parenthesizes a nested `And`/`Or` (`parse_condition(&cond.to_string()) == cond`
+/// holds for every `Condition` whose `And`/`Or` nodes have at least two operands, not
+/// just the flat, unparenthesized ones the bare `?` DSL can construct directly; a
+/// one-operand `And`/`Or` re-parses as its bare operand).
+fn parse_condition(s: &str) -> StdResult<Condition, String> {
+    let (condition, rest) = parse_or(s)?;
+    if !rest.is_empty() {
+        return Err(format!("Cannot parse condition from \"{s}\""));
+    }
+    Ok(condition)
+}
+
+fn parse_or(s: &str) -> StdResult<(Condition, &str), String> {
+    let (first, mut rest) = parse_and(s)?;
+    let mut operands = vec![first];
+    while let Some(r) = rest.strip_prefix('|') {
+        let (next, r) = parse_and(r)?;
+        operands.push(next);
+        rest = r;
+    }
+    Ok((
+        if operands.len() == 1 {
+            operands.into_iter().next().unwrap()
+        } else {
+            Condition::Or(operands)
+        },
+        rest,
+    ))
+}
+
+fn parse_and(s: &str) -> StdResult<(Condition, &str), String> {
+    let (first, mut rest) = parse_atom(s)?;
+    let mut operands = vec![first];
+    while let Some(r) = rest.strip_prefix('&') {
+        let (next, r) = parse_atom(r)?;
+        operands.push(next);
+        rest = r;
+    }
+    Ok((
+        if operands.len() == 1 {
+            operands.into_iter().next().unwrap()
+        } else {
+            Condition::And(operands)
+        },
+        rest,
+    ))
+}
+
+fn parse_atom(s: &str) -> StdResult<(Condition, &str), String> {
+    let err = || format!("Cannot parse condition from \"{s}\"");
+
+    if let Some(rest) = s.strip_prefix('!') {
+        let (inner, rest) = parse_atom(rest)?;
+        return Ok((Condition::Not(Box::new(inner)), rest));
+    }
+    if let Some(rest) = s.strip_prefix('(') {
+        let (inner, rest) = parse_or(rest)?;
+        let rest = rest.strip_prefix(')').ok_or_else(err)?;
+        return Ok((inner, rest));
+    }
+
+    // A bare sequence reference, `$<id>` optionally followed by `+`, terminated by the
+    // next `&`, `|`, `)` or the end of the string.
+    let end = s.find(['&', '|', ')']).unwrap_or(s.len());
+    let (atom, rest) = s.split_at(end);
+    if atom.is_empty() {
+        return Err(err());
+    }
+    let non_empty = atom.ends_with('+');
+    let seq_spec = atom.strip_suffix('+').unwrap_or(atom);
+    let seq = match Piece::extract_id(seq_spec) {
+        Some(Piece::Sequence { id, .. }) => id,
+        _ => return Err(err()),
+    };
+    let condition = if non_empty {
+        Condition::SequenceNonEmpty(seq)
+    } else {
+        Condition::HasSequence(seq)
+    };
+    Ok((condition, rest))
+}
+
+/// Error produced while lexing a piece or template DSL string. Carries the byte
+/// offset (within the string that was being parsed) of the token that could not be
+/// parsed, so callers can point at the exact location of a malformed spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TemplateParseError {
+    message: String,
+    offset: usize,
+}
+
+impl std::fmt::Display for TemplateParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at byte offset {})", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for TemplateParseError {}
+
+impl From<TemplateParseError> for String {
+    fn from(e: TemplateParseError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Split a single piece token into its `id`, optional `:type_id` and optional
+/// `?condition` sections, honouring a leading single/double quote around `id`.
+///
+/// A quoted id is read up to its closing (unescaped) matching quote, which lets an
+/// id contain whitespace, `:` or `?` literally, e.g. `"[weird:id]":1`. Inside a quoted
+/// id, and in the unquoted form, a backslash escapes the following character (`\$`,
+/// `\:`, `\?`, `\'`, `\"` or `\\`) so it's taken literally instead of ending the id or
+/// being interpreted by [`Piece::extract_id`].
+fn lex_piece(s: &str) -> StdResult<(String, Option<String>, Option<String>), TemplateParseError> {
+    let err = |offset: usize, message: &str| TemplateParseError {
+        message: message.to_string(),
+        offset,
+    };
+
+    let chars = s.char_indices().collect::<Vec<_>>();
+    let mut id = String::new();
+    let mut i = 0;
+
+    if let Some(&(_, quote)) = chars.first() {
+        if quote == '\'' || quote == '"' {
+            i = 1;
+            let mut closed = false;
+            while i < chars.len() {
+                let (offset, c) = chars[i];
+                if c == '\\' && i + 1 < chars.len() {
+                    id.push(chars[i + 1].1);
+                    i += 2;
+                    continue;
+                }
+                if c == quote {
+                    i += 1;
+                    closed = true;
+                    break;
+                }
+                let _ = offset;
+                id.push(c);
+                i += 1;
+            }
+            if !closed {
+                return Err(err(chars[0].0, "Unterminated quote in piece id"));
+            }
+        }
+    }
+
+    if i == 0 {
+        // Unquoted id: read up to the first unescaped `:` or `?`.
+        while i < chars.len() {
+            let (_, c) = chars[i];
+            if c == '\\' && i + 1 < chars.len() {
+                id.push(chars[i + 1].1);
+                i += 2;
+                continue;
+            }
+            if c == ':' || c == '?' {
+                break;
+            }
+            id.push(c);
+            i += 1;
+        }
+    }
+
+    let rest_offset = chars.get(i).map(|&(o, _)| o).unwrap_or(s.len());
+    let rest = &s[rest_offset..];
+
+    let (type_part, condition_part) = match rest.split_once('?') {
+        Some((type_part, cond_part)) => (type_part, Some(cond_part)),
+        None => (rest, None),
+    };
+    let type_id = match type_part.strip_prefix(':') {
+        Some(type_id) => Some(type_id.to_owned()),
+        None if type_part.is_empty() => None,
+        None => {
+            return Err(err(
+                rest_offset,
+                &format!("Unexpected trailing characters in piece \"{s}\""),
+            ))
+        }
+    };
+
+    Ok((id, type_id, condition_part.map(str::to_owned)))
+}
+
+/// Render a `SpecialToken` id for use in the DSL, quoting and escaping it if needed
+/// so it re-parses to the same id: namely if it's empty, starts with a quote, `$` or
+/// `(` (which [`lex_piece`]/[`Piece::extract_id`]/[`Piece::parse_optional`] would
+/// otherwise mistake for the start of a quoted id, the `$` sigil or an `Optional`
+/// group), or contains whitespace, `:`, `?` or `\`.
+fn render_special_token_id(id: &str) -> String {
+    let needs_quoting = id.is_empty()
+        || id.starts_with('\'')
+        || id.starts_with('"')
+        || id.starts_with('$')
+        || id.starts_with('(')
+        || id.chars().any(|c| matches!(c, ':' | '?' | '\\') || c.is_whitespace());
+    if !needs_quoting {
+        return id.to_owned();
+    }
+
+    let mut quoted = String::with_capacity(id.len() + 2);
+    quoted.push('"');
+    for c in id.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl std::fmt::Display for Piece {
+    /// Renders the `$A:0` / `[CLS]:0` DSL syntax that [`Piece`]'s `TryFrom<&str>`
+    /// parses, eliding the `:type_id` suffix when it's the default `0`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sequence {
+                id,
+                type_id,
+                condition,
+            } => {
+                write!(f, "${id}")?;
+                if *type_id != 0 {
+                    write!(f, ":{type_id}")?;
+                }
+                if let Some(condition) = condition {
+                    write!(f, "?{condition}")?;
+                }
+                Ok(())
+            }
+            Self::SpecialToken {
+                id,
+                type_id,
+                condition,
+            } => {
+                write!(f, "{}", render_special_token_id(id))?;
+                if *type_id != 0 {
+                    write!(f, ":{type_id}")?;
+                }
+                if let Some(condition) = condition {
+                    write!(f, "?{condition}")?;
+                }
+                Ok(())
+            }
+            Self::Optional { when, pieces } => {
+                write!(f, "({})@{when}", pieces.iter().join(" "))
+            }
         }
     }
 }
@@ -148,26 +664,97 @@ impl TryFrom<String> for Piece {
     type Error = String;
 
     fn try_from(s: String) -> StdResult<Self, Self::Error> {
-        let parts = s.split(':').collect::<Vec<_>>();
+        Self::try_from(s.as_str())
+    }
+}
+
+impl TryFrom<&str> for Piece {
+    type Error = String;
+
+    fn try_from(s: &str) -> StdResult<Self, Self::Error> {
+        if let Some(rest) = s.strip_prefix('(') {
+            return Self::parse_optional(rest, s);
+        }
+
+        let (id, type_id, condition) = lex_piece(s).map_err(|e| e.to_string())?;
+        let condition = condition.as_deref().map(parse_condition).transpose()?;
 
         let err = || format!("Cannot build Piece from string \"{s}\"");
-        match parts.as_slice() {
-            [id, type_id] => {
+        // A quoted or backslash-escaped first character is taken literally, so such an
+        // id is always a special token, even when it starts with the `$` sigil.
+        let piece = if s.starts_with(['"', '\'', '\\']) {
+            Self::SpecialToken {
+                id,
+                type_id: 0,
+                condition: None,
+            }
+        } else {
+            Self::extract_id(&id).ok_or_else(err)?
+        };
+        let piece = match type_id {
+            Some(type_id) => {
                 let type_id: u32 = type_id.parse().map_err(|_| err())?;
-                let piece = Self::extract_id(id).ok_or_else(err)?;
-                Ok(piece.with_type_id(type_id))
+                piece.with_type_id(type_id)
             }
-            [id] => Self::extract_id(id).ok_or_else(err),
-            _ => Err(err()),
-        }
+            None => piece,
+        };
+        Ok(piece.with_condition(condition))
     }
 }
 
-impl TryFrom<&str> for Piece {
-    type Error = String;
+impl Piece {
+    /// Parses the body of a `(...)@<seq>` group (i.e. everything after the opening
+    /// `(` of `original`) into an [`Piece::Optional`]: the inner pieces are
+    /// whitespace-split with [`lex_template`] just like a top-level [`Template`], and
+    /// the matching (non-nested) `)` is found by scanning for the first unquoted `)`.
+    fn parse_optional(rest: &str, original: &str) -> StdResult<Self, String> {
+        let err = || format!("Cannot build Piece from string \"{original}\"");
+
+        let chars = rest.char_indices().collect::<Vec<_>>();
+        let mut i = 0;
+        let mut quote: Option<char> = None;
+        let mut close = None;
+        while i < chars.len() {
+            let (offset, c) = chars[i];
+            match quote {
+                Some(q) => {
+                    if c == '\\' && i + 1 < chars.len() {
+                        i += 2;
+                    } else {
+                        if c == q {
+                            quote = None;
+                        }
+                        i += 1;
+                    }
+                }
+                None => {
+                    if c == '\'' || c == '"' {
+                        quote = Some(c);
+                        i += 1;
+                    } else if c == ')' {
+                        close = Some(offset);
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+        }
+        let close = close.ok_or_else(err)?;
+        let inner = &rest[..close];
+        let when_spec = rest[close + 1..].strip_prefix('@').ok_or_else(err)?;
 
-    fn try_from(s: &str) -> StdResult<Self, Self::Error> {
-        Piece::try_from(s.to_owned())
+        let when = match Self::extract_id(&format!("${when_spec}")) {
+            Some(Self::Sequence { id, .. }) => id,
+            _ => return Err(err()),
+        };
+        let pieces = lex_template(inner)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|(token, _)| Self::try_from(token.as_str()))
+            .collect::<StdResult<Vec<_>, _>>()?;
+
+        Ok(Self::Optional { when, pieces })
     }
 }
 
@@ -277,11 +864,126 @@ impl TryFrom<String> for Template {
     }
 }
 
+/// Split a template DSL string into its whitespace-separated piece tokens, treating
+/// a single/double-quoted span as atomic so a quoted id may itself contain whitespace
+/// (e.g. `"A complex special token:"`). A backslash inside or outside a quoted span
+/// escapes the next character, so `\ ` keeps a literal space from splitting a token.
+/// Each returned token retains its start byte offset in `s`, for error reporting.
+fn lex_template(s: &str) -> StdResult<Vec<(String, usize)>, TemplateParseError> {
+    let chars = s.char_indices().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (_, c) = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let (token_start, _) = chars[i];
+        let mut quote: Option<char> = None;
+        // Once opened by a `(` as the first character of a token, whitespace inside
+        // a group no longer splits the token, so a parenthesized `Piece::Optional`
+        // group (e.g. `($B:1 [SEP]:1)@B`) lexes as a single token. Groups don't nest.
+        let mut in_group = false;
+        let mut token_end = s.len();
+        loop {
+            if i >= chars.len() {
+                if let Some(q) = quote {
+                    return Err(TemplateParseError {
+                        message: format!("Unterminated {q} quote in template"),
+                        offset: token_start,
+                    });
+                }
+                if in_group {
+                    return Err(TemplateParseError {
+                        message: "Unterminated '(' group in template".to_string(),
+                        offset: token_start,
+                    });
+                }
+                token_end = s.len();
+                break;
+            }
+            let (offset, c) = chars[i];
+            match quote {
+                Some(q) => {
+                    if c == '\\' && i + 1 < chars.len() {
+                        i += 2;
+                    } else {
+                        if c == q {
+                            quote = None;
+                        }
+                        i += 1;
+                    }
+                }
+                None => {
+                    if c == '\'' || c == '"' {
+                        quote = Some(c);
+                        i += 1;
+                    } else if in_group {
+                        if c == ')' {
+                            in_group = false;
+                        }
+                        i += 1;
+                    } else if c.is_whitespace() {
+                        token_end = offset;
+                        i += 1;
+                        break;
+                    } else if c == '(' && offset == token_start {
+                        in_group = true;
+                        i += 1;
+                    } else if c == '\\' && i + 1 < chars.len() {
+                        i += 2;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+        }
+
+        tokens.push((s[token_start..token_end].to_owned(), token_start));
+    }
+
+    Ok(tokens)
+}
+
 impl TryFrom<&str> for Template {
     type Error = String;
 
     fn try_from(s: &str) -> StdResult<Self, Self::Error> {
-        Self::try_from(s.split(' ').collect::<Vec<_>>())
+        let tokens = lex_template(s).map_err(|e| e.to_string())?;
+        Ok(Self(
+            tokens
+                .into_iter()
+                .map(|(token, offset)| {
+                    Piece::try_from(token.as_str()).map_err(|e| {
+                        format!("Cannot build Piece from string \"{token}\" (at byte offset {offset}): {e}")
+                    })
+                })
+                .collect::<StdResult<Vec<_>, Self::Error>>()?,
+        ))
+    }
+}
+
+impl std::fmt::Display for Template {
+    /// Renders the compact, space-separated `$A:0`-style DSL string accepted by
+    /// `TryFrom<&str>`, quoting any piece id that needs it so the result re-parses to
+    /// an identical `Template`: `Template::try_from(t.to_string()).unwrap() == t`.
+    /// This does not hold for an `Optional` group whose pieces contain a `)` outside
+    /// quotes (such as a parenthesized condition): the group ends at the first such
+    /// `)`, so its rendering fails to re-parse.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().join(" "))
+    }
+}
+
+impl Template {
+    /// Render this template back to the compact DSL string that `TryFrom<&str>`
+    /// parses, e.g. `"[CLS] $A [SEP] $B:1 [SEP]:1"` (a `:0` suffix is elided). Equivalent to
+    /// `self.to_string()`.
+    pub fn to_template_string(&self) -> String {
+        self.to_string()
     }
 }
 
@@ -349,12 +1051,17 @@ pub struct TemplateProcessing {
     added_pair: usize,
     #[builder(setter(into), default)]
     special_tokens: Tokens,
+    /// Templates for arities beyond the usual single/pair, keyed by the number of input
+    /// sequences they apply to.
+    #[builder(setter(custom), default)]
+    #[serde(default, skip_serializing_if = "AHashMap::is_empty")]
+    templates: AHashMap<usize, Template>,
 }
 
 impl TemplateProcessing {
     // Getter for `single`
     pub fn get_single(&self) -> String {
-        format!("{:?}", self.single)
+        self.single.to_template_string()
     }
 
     // Setter for `single`
@@ -401,6 +1108,20 @@ impl TemplateProcessing {
     pub fn set_special_tokens(&mut self, special_tokens: Tokens) {
         self.special_tokens = special_tokens;
     }
+
+    /// Serialize to the compact, human-friendly representation that stores `single`
+    /// and `pair` as `$A:0`-style DSL strings instead of the default, verbose
+    /// piece-array JSON shape produced by `serde_json::to_string`.
+    pub fn to_compact_json(&self) -> Result<String> {
+        let compact = CompactTemplateProcessing::from(self);
+        Ok(serde_json::to_string(&compact)?)
+    }
+
+    /// Parse a document produced by [`to_compact_json`](Self::to_compact_json).
+    pub fn from_compact_json(s: &str) -> Result<Self> {
+        let compact: CompactTemplateProcessing = serde_json::from_str(s)?;
+        Self::try_from(compact).map_err(Into::into)
+    }
 }
 
 impl From<&str> for TemplateProcessingBuilderError {
@@ -424,6 +1145,8 @@ struct TemplateProcessingDeserializer {
     single: Template,
     pair: Template,
     special_tokens: Tokens,
+    #[serde(default)]
+    templates: AHashMap<usize, Template>,
 }
 impl From<TemplateProcessingDeserializer> for TemplateProcessing {
     fn from(t: TemplateProcessingDeserializer) -> Self {
@@ -435,25 +1158,119 @@ impl From<TemplateProcessingDeserializer> for TemplateProcessing {
             added_single,
             added_pair,
             special_tokens: t.special_tokens,
+            templates: t.templates,
+        }
+    }
+}
+
+/// An opt-in, human-friendly serde representation of `TemplateProcessing` that stores
+/// `single`/`pair`, as well as any N-ary `templates` beyond the usual single/pair, as
+/// their compact `$A:0`-style DSL strings (see `Template::to_template_string`) instead
+/// of the default, verbose piece-array JSON shape. Use
+/// `TemplateProcessing::to_compact_json`/`from_compact_json` rather than this type
+/// directly.
+#[derive(Serialize, Deserialize)]
+struct CompactTemplateProcessing {
+    single: String,
+    pair: String,
+    special_tokens: Tokens,
+    #[serde(default, skip_serializing_if = "AHashMap::is_empty")]
+    templates: AHashMap<usize, String>,
+}
+
+impl From<&TemplateProcessing> for CompactTemplateProcessing {
+    fn from(t: &TemplateProcessing) -> Self {
+        Self {
+            single: t.single.to_template_string(),
+            pair: t.pair.to_template_string(),
+            special_tokens: t.special_tokens.clone(),
+            templates: t
+                .templates
+                .iter()
+                .map(|(n, tmpl)| (*n, tmpl.to_template_string()))
+                .collect(),
         }
     }
 }
 
-/// Count the number of added tokens in the given template
+impl TryFrom<CompactTemplateProcessing> for TemplateProcessing {
+    type Error = String;
+
+    fn try_from(c: CompactTemplateProcessing) -> StdResult<Self, Self::Error> {
+        let mut builder = TemplateProcessing::builder()
+            .try_single(c.single)?
+            .try_pair(c.pair)?
+            .special_tokens(c.special_tokens);
+        for (n, template) in c.templates {
+            builder = builder.try_nseq(n, template)?;
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+}
+
+/// Count the number of added tokens in the given template.
+///
+/// `Optional` pieces are counted as if they were always emitted, so this is an upper
+/// bound rather than an exact count whenever the template uses them; call
+/// [`TemplateProcessing::added_tokens_for_encodings`] for the exact figure given actual
+/// input.
 fn count_added(container: &Template, special_tokens: Option<&Tokens>) -> usize {
-    container
-        .0
+    count_added_pieces(&container.0, special_tokens)
+}
+
+fn count_added_pieces(pieces: &[Piece], special_tokens: Option<&Tokens>) -> usize {
+    pieces
         .iter()
         .map(|p| match p {
             Piece::Sequence { .. } => 0,
             Piece::SpecialToken { id, .. } => {
                 special_tokens.map_or(0, |spt| spt.0.get(id).map_or(0, |s| s.ids.len()))
             }
+            Piece::Optional { pieces, .. } => count_added_pieces(pieces, special_tokens),
         })
         .sum()
 }
 
 impl TemplateProcessingBuilder {
+    /// Set the template to use whenever exactly `n` input sequences are given to
+    /// [`PostProcessor::process_encodings`]. This is how templates for arities other than
+    /// single (1) and pair (2) are registered.
+    pub fn try_nseq<T>(mut self, n: usize, template: T) -> StdResult<Self, String>
+    where
+        T: TryInto<Template, Error = String>,
+    {
+        let template = template.try_into()?;
+        self.templates
+            .get_or_insert_with(AHashMap::new)
+            .insert(n, template);
+        Ok(self)
+    }
+
+    /// Build an N-ary template, inferring its arity from the highest sequence index
+    /// it references (a template mentioning up to `$C`/`$seq2` applies to 3 input
+    /// sequences). This is sugar over [`try_nseq`](Self::try_nseq) for templates
+    /// spanning three or more segments, e.g. context + question + answer, that don't
+    /// need the arity spelled out separately. Note that a bare `$2` does *not* count
+    /// as referencing sequence 2 here — see the module-level docs on why the digit
+    /// form is reserved for `type_id`-on-`A`.
+    pub fn try_multi<T>(self, template: Vec<T>) -> StdResult<Self, String>
+    where
+        T: TryInto<Piece, Error = String>,
+    {
+        let template = Template::try_from(template)?;
+        let n = flatten_pieces(&template.0)
+            .into_iter()
+            .filter_map(|p| match p {
+                Piece::Sequence { id, .. } => Some(id.index() + 1),
+                Piece::SpecialToken { .. } | Piece::Optional { .. } => None,
+            })
+            .max()
+            .ok_or_else(|| {
+                "Template for `try_multi` must reference at least one sequence".to_string()
+            })?;
+        self.try_nseq(n, template)
+    }
+
     fn default_added(&self, is_single: bool) -> usize {
         let container = if is_single {
             self.single.as_ref()
@@ -467,22 +1284,25 @@ impl TemplateProcessingBuilder {
 
     fn validate(&self) -> std::result::Result<(), String> {
         let pair_has_both = self.pair.as_ref().is_none_or(|pair| {
-            let mut has_a = false;
-            let mut has_b = false;
-            for piece in &pair.0 {
-                if let Piece::Sequence {
-                    id: Sequence::A, ..
-                } = piece
-                {
-                    has_a = true;
-                }
-                if let Piece::Sequence {
-                    id: Sequence::B, ..
-                } = piece
-                {
-                    has_b = true;
-                }
-            }
+            let pieces = flatten_pieces(&pair.0);
+            let has_a = pieces.iter().any(|p| {
+                matches!(
+                    p,
+                    Piece::Sequence {
+                        id: Sequence::A,
+                        ..
+                    }
+                )
+            });
+            let has_b = pieces.iter().any(|p| {
+                matches!(
+                    p,
+                    Piece::Sequence {
+                        id: Sequence::B,
+                        ..
+                    }
+                )
+            });
             has_a && has_b
         });
         if !pair_has_both {
@@ -501,15 +1321,23 @@ impl TemplateProcessingBuilder {
             }
         };
 
-        let empty = [];
-        let missing: AHashSet<&str> = self
-            .single
+        let empty: Template = Template(vec![]);
+        let single_pieces = flatten_pieces(self.single.as_ref().unwrap_or(&empty).0.as_slice());
+        let pair_pieces = flatten_pieces(self.pair.as_ref().unwrap_or(&empty).0.as_slice());
+        let nseq_pieces = self
+            .templates
             .as_ref()
-            .map_or(empty.iter(), |s| s.0.iter())
-            .chain(self.pair.as_ref().map_or(empty.iter(), |s| s.0.iter()))
+            .into_iter()
+            .flat_map(|templates| templates.values())
+            .flat_map(|template| flatten_pieces(template.0.as_slice()));
+        let missing: AHashSet<&str> = single_pieces
+            .into_iter()
+            .chain(pair_pieces)
+            .chain(nseq_pieces)
             .filter_map(|piece| match piece {
                 Piece::Sequence { .. } => None,
                 Piece::SpecialToken { id, .. } => check(id.as_ref()),
+                Piece::Optional { .. } => None,
             })
             .collect::<AHashSet<_>>();
 
@@ -524,6 +1352,18 @@ impl TemplateProcessingBuilder {
     }
 }
 
+/// Flatten a list of pieces, recursing into any `Optional` groups so their nested
+/// pieces are visited too; the `Optional` wrapper itself is not included.
+fn flatten_pieces(pieces: &[Piece]) -> Vec<&Piece> {
+    pieces
+        .iter()
+        .flat_map(|piece| match piece {
+            Piece::Optional { pieces, .. } => flatten_pieces(pieces),
+            other => vec![other],
+        })
+        .collect()
+}
+
 impl Default for TemplateProcessing {
     fn default() -> Self {
         Self {
@@ -532,6 +1372,7 @@ impl Default for TemplateProcessing {
             added_single: 0,
             added_pair: 0,
             special_tokens: Tokens::default(),
+            templates: AHashMap::new(),
         }
     }
 }
@@ -541,6 +1382,77 @@ impl TemplateProcessing {
         TemplateProcessingBuilder::default()
     }
 
+    /// Render a single [`Piece`] against the input `encodings`, producing the (zero or
+    /// more) fragments it contributes. A plain `Sequence`/`SpecialToken` contributes at
+    /// most one fragment; an `Optional` recursively renders its nested pieces (so it
+    /// can contribute several, or none if it's skipped).
+    fn render_piece(
+        &self,
+        piece: &Piece,
+        encodings: &mut [Encoding],
+        add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>> {
+        if piece.is_skipped(encodings) {
+            return Ok(vec![]);
+        }
+
+        match piece {
+            Piece::Sequence { id, type_id, .. } => {
+                let i = id.index();
+                let encoding = encodings.get_mut(i).ok_or_else(|| {
+                    format!(
+                        "Template expects a sequence at index {} but only {} \
+                         encoding(s) were given",
+                        i,
+                        encodings.len()
+                    )
+                })?;
+                encoding.set_type_ids(vec![*type_id; encoding.len()]);
+                encoding.set_sequence_id(i);
+                // The overflow carried by the input encoding is re-derived (with
+                // the template re-applied) below, so it shouldn't also leak
+                // through this fragment's own clone.
+                let mut fragment = encoding.clone();
+                fragment.set_overflowing(vec![]);
+                Ok(vec![fragment])
+            }
+            Piece::SpecialToken { id, type_id, .. } => {
+                if add_special_tokens {
+                    let tok = &self.special_tokens.0[id]; // We already checked existence above
+                    let len = tok.ids.len();
+
+                    let encoding = Encoding::new(
+                        tok.ids.clone(),
+                        std::iter::repeat_n(*type_id, len).collect(),
+                        tok.tokens.clone(),
+                        // words
+                        std::iter::repeat_n(None, len).collect(),
+                        // offsets
+                        std::iter::repeat_n((0, 0), len).collect(),
+                        // special_tokens_mask
+                        std::iter::repeat_n(1, len).collect(),
+                        // attention_mask
+                        std::iter::repeat_n(1, len).collect(),
+                        // overflowing
+                        vec![],
+                        // sequence_range
+                        AHashMap::new(),
+                    );
+                    Ok(vec![encoding])
+                } else {
+                    Ok(vec![])
+                }
+            }
+            Piece::Optional { pieces, .. } => Ok(pieces
+                .iter()
+                .map(|p| self.render_piece(p, encodings, add_special_tokens))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect()),
+        }
+    }
+
     fn apply_template(
         &self,
         template: &[Piece],
@@ -549,98 +1461,177 @@ impl TemplateProcessing {
     ) -> Result<Vec<Encoding>> {
         let final_encodings: Vec<Encoding> = template
             .iter()
-            .flat_map(|piece| {
-                match piece {
-                    Piece::Sequence { id, type_id } => {
-                        let i = usize::from(*id != Sequence::A);
-                        let encoding = &mut encodings[i];
-                        encoding.set_type_ids(vec![*type_id; encoding.len()]);
-                        encoding.set_sequence_id(i);
-                        Some(encoding.clone())
+            .map(|piece| self.render_piece(piece, &mut encodings, add_special_tokens))
+            .collect::<Result<Vec<Vec<Encoding>>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        // Propagate the overflowing segments carried by the input encodings: each
+        // combination needs the template (and its special tokens) re-applied so that
+        // type_ids, special_tokens_mask and offsets stay consistent, just like the main
+        // encoding. The single and pair shapes are handled explicitly; any other arity
+        // goes through the general odometer-style fan-out in the last branch.
+        let mut final_encodings = final_encodings;
+        match encodings.len() {
+            1 => {
+                let encoding = encodings.pop().unwrap();
+                // Read (without disturbing `encoding`, which we still need intact below)
+                // the list of overflow segments carried by the input.
+                let main_overflowing = encoding.clone().take_overflowing();
+
+                let overflowing = main_overflowing
+                    .iter()
+                    .map(|main_o| {
+                        self.apply_template(template, vec![main_o.clone()], add_special_tokens)
+                            .map(|fragments| Encoding::merge(fragments, false))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                if let Some(first) = final_encodings.first_mut() {
+                    first.set_overflowing(overflowing);
+                }
+            }
+            2 => {
+                let pair = encodings.pop().unwrap();
+                let encoding = encodings.pop().unwrap();
+                // Neither `encoding` nor `pair` is modified here: each still carries its
+                // own overflowing segments, so re-applying the template to a clone lets
+                // the recursive call derive the matching nested cross-product on its own.
+                let main_overflowing = encoding.clone().take_overflowing();
+                let pair_overflowing = pair.clone().take_overflowing();
+
+                let mut overflowing = Vec::new();
+                for main_o in &main_overflowing {
+                    // 1. This main overflow, combined with the pair (which still carries
+                    //    its own overflow, producing the nested cross-product below)
+                    overflowing.push(
+                        self.apply_template(
+                            template,
+                            vec![main_o.clone(), pair.clone()],
+                            add_special_tokens,
+                        )
+                        .map(|fragments| Encoding::merge(fragments, false))?,
+                    );
+                    // 2. This main overflow, combined with each pair overflow directly
+                    for pair_o in &pair_overflowing {
+                        overflowing.push(
+                            self.apply_template(
+                                template,
+                                vec![main_o.clone(), pair_o.clone()],
+                                add_special_tokens,
+                            )
+                            .map(|fragments| Encoding::merge(fragments, false))?,
+                        );
+                    }
+                }
+                // 3. The main encoding (still carrying its own overflow), combined with
+                //    each pair overflow
+                for pair_o in &pair_overflowing {
+                    overflowing.push(
+                        self.apply_template(
+                            template,
+                            vec![encoding.clone(), pair_o.clone()],
+                            add_special_tokens,
+                        )
+                        .map(|fragments| Encoding::merge(fragments, false))?,
+                    );
+                }
+
+                if let Some(first) = final_encodings.first_mut() {
+                    first.set_overflowing(overflowing);
+                }
+            }
+            _ => {
+                // General N-ary fan-out: each sequence contributes a list of
+                // alternatives (itself, unchanged, plus each of its own overflow
+                // segments). Every combination except "every sequence picks itself"
+                // (which is exactly `final_encodings`, already computed above) gets
+                // the template re-applied, recursively deriving any further nested
+                // overflow along the way.
+                let alternatives: Vec<Vec<Encoding>> = encodings
+                    .iter()
+                    .map(|e| {
+                        let mut alts = vec![e.clone()];
+                        alts.extend(e.clone().take_overflowing());
+                        alts
+                    })
+                    .collect();
+
+                let mut overflowing = Vec::new();
+                let mut picks = vec![0usize; alternatives.len()];
+                'combos: loop {
+                    if picks.iter().any(|&i| i != 0) {
+                        let combo: Vec<Encoding> = picks
+                            .iter()
+                            .enumerate()
+                            .map(|(seq, &i)| alternatives[seq][i].clone())
+                            .collect();
+                        overflowing.push(
+                            self.apply_template(template, combo, add_special_tokens)
+                                .map(|fragments| Encoding::merge(fragments, false))?,
+                        );
                     }
-                    Piece::SpecialToken { id, type_id } => {
-                        if add_special_tokens {
-                            let tok = &self.special_tokens.0[id]; // We already checked existence above
-                            let len = tok.ids.len();
-
-                            let encoding = Encoding::new(
-                                tok.ids.clone(),
-                                std::iter::repeat_n(*type_id, len).collect(),
-                                tok.tokens.clone(),
-                                // words
-                                std::iter::repeat_n(None, len).collect(),
-                                // offsets
-                                std::iter::repeat_n((0, 0), len).collect(),
-                                // special_tokens_mask
-                                std::iter::repeat_n(1, len).collect(),
-                                // attention_mask
-                                std::iter::repeat_n(1, len).collect(),
-                                // overflowing
-                                vec![],
-                                // sequence_range
-                                AHashMap::new(),
-                            );
-                            Some(encoding)
-                        } else {
-                            None
+
+                    // Advance `picks` like an odometer over `alternatives`.
+                    let mut seq = 0;
+                    loop {
+                        if seq == picks.len() {
+                            break 'combos;
+                        }
+                        picks[seq] += 1;
+                        if picks[seq] < alternatives[seq].len() {
+                            break;
                         }
+                        picks[seq] = 0;
+                        seq += 1;
                     }
                 }
-            })
-            .collect();
 
-        //let mut pair = if encodings.len() > 1 {
-        //    Some(encodings.pop().unwrap())
-        //} else {
-        //    None
-        //};
-        //let mut encoding = encodings.pop().unwrap();
-
-        //let pair_overflowing = pair.as_mut().map_or(vec![], |e| e.take_overflowing());
-        //let mut overflowing: Vec<Encoding> = encoding
-        //    .take_overflowing()
-        //    .iter()
-        //    .map(|encoding| -> Result<Vec<Encoding>> {
-        //        // 1. The pair itself
-        //        let mut overflowings = self.apply_template(
-        //            template,
-        //            if encodings.len() > 1 {
-        //                vec![encoding.clone(), encodings[1].clone()]
-        //            } else {
-        //                vec![encoding.clone()]
-        //            },
-        //            add_special_tokens,
-        //        )?;
-
-        //        // 2. Its overflowings
-        //        for other_o in &pair_overflowing {
-        //            overflowings.extend(self.apply_template(
-        //                template,
-        //                vec![encoding.clone(), other_o.clone()],
-        //                add_special_tokens,
-        //            )?);
-        //        }
-
-        //        Ok(overflowings)
-        //    })
-        //    .collect::<Result<Vec<Vec<Encoding>>>>()?
-        //    .into_iter()
-        //    .flatten()
-        //    .collect();
-        //// We also need to combine the first sequence with all other overflowings
-        //overflowing.extend(
-        //    pair_overflowing
-        //        .into_iter()
-        //        .map(|pair| {
-        //            self.apply_template(template, vec![encoding.clone(), pair], add_special_tokens)
-        //        })
-        //        .collect::<Result<Vec<_>>>()?
-        //        .into_iter()
-        //        .flatten(),
-        //);
+                if let Some(first) = final_encodings.first_mut() {
+                    first.set_overflowing(overflowing);
+                }
+            }
+        }
 
         Ok(final_encodings)
     }
+
+    /// Like [`PostProcessor::added_tokens`], but exact even when the template uses
+    /// `Optional` pieces: counts only the special tokens that would actually be
+    /// emitted for this specific set of input `encodings`, rather than assuming every
+    /// `Optional` group is always emitted.
+    pub fn added_tokens_for_encodings(&self, encodings: &[Encoding]) -> usize {
+        let template = match encodings.len() {
+            1 => &self.single,
+            2 => &self.pair,
+            n => match self.templates.get(&n) {
+                Some(template) => template,
+                None => return 0,
+            },
+        };
+        self.added_tokens_for_pieces(&template.0, encodings)
+    }
+
+    fn added_tokens_for_pieces(&self, pieces: &[Piece], encodings: &[Encoding]) -> usize {
+        pieces
+            .iter()
+            .map(|piece| {
+                if piece.is_skipped(encodings) {
+                    return 0;
+                }
+                match piece {
+                    Piece::Sequence { .. } => 0,
+                    Piece::SpecialToken { id, .. } => {
+                        self.special_tokens.0.get(id).map_or(0, |s| s.ids.len())
+                    }
+                    Piece::Optional { pieces, .. } => {
+                        self.added_tokens_for_pieces(pieces, encodings)
+                    }
+                }
+            })
+            .sum()
+    }
 }
 
 impl PostProcessor for TemplateProcessing {
@@ -678,7 +1669,11 @@ impl PostProcessor for TemplateProcessing {
         let template = match encodings.len() {
             2 => &self.pair.0,
             1 => &self.single.0,
-            _ => todo!(),
+            n => self
+                .templates
+                .get(&n)
+                .map(|t| t.0.as_slice())
+                .ok_or_else(|| format!("No template was registered for {n} input sequences"))?,
         };
         let encodings = self.apply_template(template, encodings, add_special_tokens)?;
         Ok(encodings)
@@ -696,6 +1691,7 @@ mod tests {
         let seq_0 = Piece::Sequence {
             id: Sequence::A,
             type_id: 0,
+            condition: None,
         };
         let seq_0_s = r#"{"Sequence":{"id":"A","type_id":0}}"#;
 
@@ -705,6 +1701,7 @@ mod tests {
         let seq_1 = Piece::Sequence {
             id: Sequence::B,
             type_id: 1,
+            condition: None,
         };
         let seq_1_s = r#"{"Sequence":{"id":"B","type_id":1}}"#;
         assert_eq!(serde_json::to_string(&seq_1).unwrap(), seq_1_s);
@@ -713,6 +1710,7 @@ mod tests {
         let spe = Piece::SpecialToken {
             id: "[CLS]".into(),
             type_id: 0,
+            condition: None,
         };
         let spe_s = r#"{"SpecialToken":{"id":"[CLS]","type_id":0}}"#;
         assert_eq!(serde_json::to_string(&spe).unwrap(), spe_s);
@@ -724,42 +1722,191 @@ mod tests {
         assert_eq!(
             Ok(Piece::Sequence {
                 id: Sequence::A,
-                type_id: 0
+                type_id: 0,
+                condition: None,
             }),
             "$".try_into()
         );
         assert_eq!(
             Ok(Piece::Sequence {
                 id: Sequence::B,
-                type_id: 0
+                type_id: 0,
+                condition: None,
             }),
             "$B".try_into()
         );
         assert_eq!(
             Ok(Piece::Sequence {
                 id: Sequence::A,
-                type_id: 1
+                type_id: 1,
+                condition: None,
             }),
             "$1".try_into()
         );
         assert_eq!(
             Ok(Piece::Sequence {
                 id: Sequence::B,
-                type_id: 2
+                type_id: 2,
+                condition: None,
             }),
             "$B:2".try_into()
         );
         assert_eq!(
             Ok(Piece::Sequence {
                 id: Sequence::A,
-                type_id: 1
+                type_id: 1,
+                condition: None,
             }),
             "$:1".try_into()
         );
-        assert!(Piece::try_from("$C:1").is_err());
+        // `$C` now designates the third sequence (index 2), so this parses rather
+        // than erroring as it used to when only A/B were supported.
+        assert_eq!(
+            Ok(Piece::Sequence {
+                id: Sequence::from_index(2),
+                type_id: 1,
+                condition: None,
+            }),
+            "$C:1".try_into()
+        );
         assert!(Piece::try_from("$A:").is_err());
     }
 
+    #[test]
+    fn piece_condition() {
+        let guarded: Piece = "[SEP]:1?$B".try_into().unwrap();
+        assert_eq!(
+            guarded,
+            Piece::SpecialToken {
+                id: "[SEP]".into(),
+                type_id: 1,
+                condition: Some(Condition::HasSequence(Sequence::B)),
+            }
+        );
+
+        let negated: Piece = "[SEP]?!$B".try_into().unwrap();
+        assert_eq!(
+            negated,
+            Piece::SpecialToken {
+                id: "[SEP]".into(),
+                type_id: 0,
+                condition: Some(Condition::Not(Box::new(Condition::HasSequence(
+                    Sequence::B
+                )))),
+            }
+        );
+
+        let non_empty: Piece = "[SEP]?$B+".try_into().unwrap();
+        assert_eq!(
+            non_empty,
+            Piece::SpecialToken {
+                id: "[SEP]".into(),
+                type_id: 0,
+                condition: Some(Condition::SequenceNonEmpty(Sequence::B)),
+            }
+        );
+    }
+
+    #[test]
+    fn piece_quoted_id() {
+        // A quoted id may contain a literal `:`, which would otherwise be taken as
+        // the start of the `type_id` section.
+        let piece: Piece = "\"[weird:id]\":1".try_into().unwrap();
+        assert_eq!(
+            piece,
+            Piece::SpecialToken {
+                id: "[weird:id]".into(),
+                type_id: 1,
+                condition: None,
+            }
+        );
+
+        let piece: Piece = "'[also:weird]'".try_into().unwrap();
+        assert_eq!(
+            piece,
+            Piece::SpecialToken {
+                id: "[also:weird]".into(),
+                type_id: 0,
+                condition: None,
+            }
+        );
+
+        assert!(Piece::try_from("\"unterminated").is_err());
+    }
+
+    #[test]
+    fn piece_escaped_id() {
+        // A backslash escapes the next character so it's taken literally instead of
+        // being interpreted as the `$` sigil or a `:`/`?` separator.
+        let piece: Piece = "\\$literal".try_into().unwrap();
+        assert_eq!(
+            piece,
+            Piece::SpecialToken {
+                id: "$literal".into(),
+                type_id: 0,
+                condition: None,
+            }
+        );
+
+        let piece: Piece = "[CLS\\:1]".try_into().unwrap();
+        assert_eq!(
+            piece,
+            Piece::SpecialToken {
+                id: "[CLS:1]".into(),
+                type_id: 0,
+                condition: None,
+            }
+        );
+    }
+
+    #[test]
+    fn template_quoted_and_escaped() {
+        // A quoted token may contain whitespace, which would otherwise split it into
+        // two tokens.
+        let template: Template = "\"A complex special token:\" $0".try_into().unwrap();
+        assert_eq!(
+            template,
+            Template(vec![
+                Piece::SpecialToken {
+                    id: "A complex special token:".into(),
+                    type_id: 0,
+                    condition: None,
+                },
+                Piece::Sequence {
+                    id: Sequence::A,
+                    type_id: 0,
+                    condition: None,
+                },
+            ])
+        );
+
+        // Existing unquoted, whitespace-separated templates keep working as before.
+        let template: Template = "[CLS] $0 [SEP]".try_into().unwrap();
+        assert_eq!(
+            template,
+            Template(vec![
+                Piece::SpecialToken {
+                    id: "[CLS]".into(),
+                    type_id: 0,
+                    condition: None,
+                },
+                Piece::Sequence {
+                    id: Sequence::A,
+                    type_id: 0,
+                    condition: None,
+                },
+                Piece::SpecialToken {
+                    id: "[SEP]".into(),
+                    type_id: 0,
+                    condition: None,
+                },
+            ])
+        );
+
+        let err = Template::try_from("\"unterminated").unwrap_err();
+        assert!(err.contains("byte offset 0"));
+    }
+
     #[test]
     fn special_token_serde() {
         let simple = SpecialToken::from(("[CLS]", 0));
@@ -803,10 +1950,12 @@ mod tests {
             Piece::Sequence {
                 id: Sequence::A,
                 type_id: 0,
+                condition: None,
             },
             Piece::SpecialToken {
                 id: "[CLS]".into(),
                 type_id: 0,
+                condition: None,
             },
         ]);
         let template_s =
@@ -818,6 +1967,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn template_to_template_string_round_trip() {
+        let cases = [
+            "$0",
+            "$A:0 $B:1",
+            "[CLS] $0 [SEP]",
+            "[CLS]:0 $A:0 [SEP]:0 $B:1 [SEP]:1",
+            "[CLS] $A [SEP] $B:1?$B [SEP]:1?$B",
+            "[CLS] $A [SEP]:1?!$B&$A+",
+        ];
+        for case in cases {
+            let template: Template = case.try_into().unwrap();
+            let rendered = template.to_template_string();
+            assert_eq!(rendered, template.to_string());
+            let reparsed: Template = rendered.as_str().try_into().unwrap();
+            assert_eq!(reparsed, template, "round-trip failed for \"{case}\"");
+        }
+
+        // Ids needing quoting/escaping round-trip too.
+        let template = Template(vec![
+            Piece::SpecialToken {
+                id: "A complex special token:".into(),
+                type_id: 1,
+                condition: None,
+            },
+            Piece::SpecialToken {
+                id: "".into(),
+                type_id: 0,
+                condition: None,
+            },
+        ]);
+        let rendered = template.to_template_string();
+        assert_eq!(rendered, "\"A complex special token:\":1 \"\"");
+        let reparsed: Template = rendered.as_str().try_into().unwrap();
+        assert_eq!(reparsed, template);
+    }
+
     #[test]
     fn tokens_serde() {
         let tokens = Tokens::from(vec![("[CLS]", 1), ("[SEP]", 0)]);
@@ -871,6 +2057,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn template_processing_compact_json() {
+        let template = tests::get_bert_template();
+        let compact = template.to_compact_json().unwrap();
+        assert_eq!(
+            compact,
+            r#"{"single":"[CLS] $A [SEP]","pair":"[CLS] $A [SEP] $B:1 [SEP]:1","special_tokens":{"[CLS]":{"id":"[CLS]","ids":[1],"tokens":["[CLS]"]},"[SEP]":{"id":"[SEP]","ids":[0],"tokens":["[SEP]"]}}}"#
+        );
+        assert_eq!(TemplateProcessing::from_compact_json(&compact).unwrap(), template);
+    }
+
     #[test]
     fn missing_special_tokens() {
         let processor = TemplateProcessing::builder()
@@ -1140,6 +2337,202 @@ mod tests {
         );
     }
 
+    #[test]
+    fn template_processing_nseq() {
+        let processor = TemplateProcessing::builder()
+            .try_single("[CLS] $0 [SEP]")
+            .unwrap()
+            .try_pair("[CLS] $A:0 [SEP] $B:1 [SEP]")
+            .unwrap()
+            .try_nseq(3, "[CLS] $A:0 [SEP] $B:1 [SEP]:1 $C:2 [SEP]:2")
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .build()
+            .unwrap();
+
+        use crate::Token;
+        let a = Encoding::from_tokens(vec![Token::new(12, "a".into(), (0, 1))], 0);
+        let b = Encoding::from_tokens(vec![Token::new(13, "b".into(), (0, 1))], 0);
+        let c = Encoding::from_tokens(vec![Token::new(14, "c".into(), (0, 1))], 0);
+
+        let fragments = processor.process_encodings(vec![a, b, c], true).unwrap();
+        let ids: Vec<u32> = fragments.iter().flat_map(|e| e.get_ids().to_vec()).collect();
+        let type_ids: Vec<u32> = fragments
+            .iter()
+            .flat_map(|e| e.get_type_ids().to_vec())
+            .collect();
+        assert_eq!(ids, vec![1, 12, 0, 13, 0, 14, 0]);
+        assert_eq!(type_ids, vec![0, 0, 0, 1, 1, 2, 2]);
+
+        // Missing a template for this arity
+        let a = Encoding::from_tokens(vec![Token::new(12, "a".into(), (0, 1))], 0);
+        let b = Encoding::from_tokens(vec![Token::new(13, "b".into(), (0, 1))], 0);
+        let c = Encoding::from_tokens(vec![Token::new(14, "c".into(), (0, 1))], 0);
+        let d = Encoding::from_tokens(vec![Token::new(15, "d".into(), (0, 1))], 0);
+        assert!(processor.process_encodings(vec![a, b, c, d], true).is_err());
+    }
+
+    #[test]
+    fn template_processing_nseq_overflowing() {
+        // The general `_ =>` branch in `apply_template` handles overflow for
+        // arbitrary arity, odometer-style: every sequence contributes itself plus
+        // each of its own overflow segments, and every combination other than "all
+        // sequences pick themselves" (the main encoding, already covered by
+        // `final_encodings`) gets the template re-applied. Mirrors
+        // `template_processing_overflowing`, but for three sequences.
+        let processor = TemplateProcessing::builder()
+            .try_single("[CLS] $0 [SEP]")
+            .unwrap()
+            .try_pair("[CLS] $A:0 [SEP] $B:1 [SEP]")
+            .unwrap()
+            .try_nseq(3, "[CLS] $A:0 [SEP] $B:1 [SEP]:1 $C:2 [SEP]:2")
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .build()
+            .unwrap();
+
+        use crate::Token;
+        let mut a = Encoding::from_tokens(vec![Token::new(12, "a".into(), (0, 1))], 0);
+        a.set_overflowing(vec![Encoding::from_tokens(
+            vec![Token::new(18, "a2".into(), (2, 3))],
+            0,
+        )]);
+        // `b` has no overflow of its own, so the odometer only ever advances `a` and
+        // `c`.
+        let b = Encoding::from_tokens(vec![Token::new(13, "b".into(), (0, 1))], 0);
+        let mut c = Encoding::from_tokens(vec![Token::new(14, "c".into(), (0, 1))], 0);
+        c.set_overflowing(vec![Encoding::from_tokens(
+            vec![Token::new(19, "c2".into(), (2, 3))],
+            0,
+        )]);
+
+        let fragments = processor.process_encodings(vec![a, b, c], true).unwrap();
+        let encoding = Encoding::merge(fragments, false);
+
+        assert_eq!(encoding.get_ids(), [1, 12, 0, 13, 0, 14, 0]);
+        assert_eq!(encoding.get_type_ids(), [0, 0, 0, 1, 1, 2, 2]);
+        assert_eq!(encoding.get_special_tokens_mask(), [1, 0, 1, 0, 1, 0, 1]);
+
+        // Every combination but "all main" (2 alternatives for `a` * 1 for `b` * 2
+        // for `c`, minus the all-main one already reflected above), in the order the
+        // odometer in `apply_template` visits them: `a` is the fastest-advancing
+        // digit, then `b`, then `c`.
+        let overflowing = encoding.get_overflowing();
+        assert_eq!(overflowing.len(), 3);
+        assert_eq!(overflowing[0].get_ids(), [1, 18, 0, 13, 0, 14, 0]);
+        assert_eq!(overflowing[1].get_ids(), [1, 12, 0, 13, 0, 19, 0]);
+        assert_eq!(overflowing[2].get_ids(), [1, 18, 0, 13, 0, 19, 0]);
+        for o in overflowing {
+            assert_eq!(o.get_type_ids(), [0, 0, 0, 1, 1, 2, 2]);
+            assert_eq!(o.get_special_tokens_mask(), [1, 0, 1, 0, 1, 0, 1]);
+        }
+    }
+
+    #[test]
+    fn template_processing_try_multi() {
+        // `try_multi` infers the arity (3) from the highest sequence index referenced,
+        // so the caller doesn't have to repeat it like `try_nseq` requires.
+        let processor = TemplateProcessing::builder()
+            .try_single("[CLS] $0 [SEP]")
+            .unwrap()
+            .try_pair("[CLS] $A:0 [SEP] $B:1 [SEP]")
+            .unwrap()
+            .try_multi(vec![
+                "[CLS]:0", "$A:0", "[SEP]:0", "$B:1", "[SEP]:1", "$C:2", "[SEP]:2",
+            ])
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .build()
+            .unwrap();
+
+        use crate::Token;
+        let a = Encoding::from_tokens(vec![Token::new(12, "a".into(), (0, 1))], 0);
+        let b = Encoding::from_tokens(vec![Token::new(13, "b".into(), (0, 1))], 0);
+        let c = Encoding::from_tokens(vec![Token::new(14, "c".into(), (0, 1))], 0);
+
+        let fragments = processor.process_encodings(vec![a, b, c], true).unwrap();
+        let ids: Vec<u32> = fragments.iter().flat_map(|e| e.get_ids().to_vec()).collect();
+        assert_eq!(ids, vec![1, 12, 0, 13, 0, 14, 0]);
+
+        // A template with no sequence piece at all has no arity to infer.
+        assert!(TemplateProcessing::builder()
+            .try_multi(vec!["[CLS]", "[SEP]"])
+            .is_err());
+    }
+
+    #[test]
+    fn template_processing_conditional_piece() {
+        // A single template handles both the single and pair cases: the second
+        // `[SEP]` (and the pair sequence itself) only materializes when `B` was
+        // actually supplied.
+        let processor = TemplateProcessing::builder()
+            .try_single("[CLS] $A [SEP] $B:1?$B [SEP]:1?$B")
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .build()
+            .unwrap();
+
+        use crate::Token;
+        let a = Encoding::from_tokens(vec![Token::new(12, "a".into(), (0, 1))], 0);
+        let fragments = processor
+            .apply_template(&processor.single.0, vec![a], true)
+            .unwrap();
+        let ids: Vec<u32> = fragments.iter().flat_map(|e| e.get_ids().to_vec()).collect();
+        assert_eq!(ids, vec![1, 12, 0]);
+    }
+
+    #[test]
+    fn piece_optional_parse_and_display() {
+        let piece = Piece::try_from("($B:1 [SEP]:1)@B").unwrap();
+        assert_eq!(
+            piece,
+            Piece::Optional {
+                when: Sequence::B,
+                pieces: vec![
+                    Piece::try_from("$B:1").unwrap(),
+                    Piece::try_from("[SEP]:1").unwrap(),
+                ],
+            }
+        );
+        assert_eq!(piece.to_string(), "($B:1 [SEP]:1)@B");
+    }
+
+    #[test]
+    fn template_processing_optional_piece() {
+        // The separator and the B sequence drop out together whenever B is empty or
+        // absent, instead of each piece needing its own `?$B` guard.
+        let processor = TemplateProcessing::builder()
+            .try_single("[CLS] $A [SEP]")
+            .unwrap()
+            .try_pair("[CLS] $A [SEP] ($B:1 [SEP]:1)@B")
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .build()
+            .unwrap();
+
+        use crate::Token;
+        let a = Encoding::from_tokens(vec![Token::new(12, "a".into(), (0, 1))], 0);
+        let b = Encoding::from_tokens(vec![Token::new(13, "b".into(), (0, 1))], 0);
+        let empty_b = Encoding::from_tokens(vec![], 0);
+
+        let with_b = processor
+            .apply_template(&processor.pair.0, vec![a.clone(), b.clone()], true)
+            .unwrap();
+        let ids: Vec<u32> = with_b.iter().flat_map(|e| e.get_ids().to_vec()).collect();
+        assert_eq!(ids, vec![1, 12, 0, 13, 0]);
+        assert_eq!(processor.added_tokens_for_encodings(&[a.clone(), b]), 3);
+
+        let without_b = processor
+            .apply_template(&processor.pair.0, vec![a.clone(), empty_b.clone()], true)
+            .unwrap();
+        let ids: Vec<u32> = without_b
+            .iter()
+            .flat_map(|e| e.get_ids().to_vec())
+            .collect();
+        assert_eq!(ids, vec![1, 12, 0]);
+        assert_eq!(processor.added_tokens_for_encodings(&[a, empty_b]), 2);
+    }
+
     #[test]
     fn expect_wrong_error_message() {
         let processor = TemplateProcessing::builder()